@@ -0,0 +1,341 @@
+use std::{collections::HashMap, fmt::Display};
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn expect_len(chunk_type: &str, data: &[u8], len: usize) -> crate::Result<()> {
+    if data.len() != len {
+        return Err(format!("{chunk_type} must be {len} bytes, found {}", data.len()).into());
+    }
+    Ok(())
+}
+
+/// The default background color from a `bKGD` chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    PaletteIndex(u8),
+    Gray(u16),
+    Rgb(u16, u16, u16),
+}
+
+impl Background {
+    pub fn parse(data: &[u8], color_type: u8) -> crate::Result<Self> {
+        match color_type {
+            3 => {
+                expect_len("bKGD", data, 1)?;
+                Ok(Self::PaletteIndex(data[0]))
+            }
+            0 | 4 => {
+                expect_len("bKGD", data, 2)?;
+                Ok(Self::Gray(u16_at(data, 0)))
+            }
+            2 | 6 => {
+                expect_len("bKGD", data, 6)?;
+                Ok(Self::Rgb(u16_at(data, 0), u16_at(data, 2), u16_at(data, 4)))
+            }
+            other => Err(format!("Unknown color type {other}").into()),
+        }
+    }
+    /// The color as `#rrggbb` (`#rrrrggggbbbb` for 16-bit images), with gray
+    /// below 8 bits scaled up. `None` for a palette index, which needs the
+    /// palette to know the color.
+    pub fn hex(&self, bit_depth: u8) -> Option<String> {
+        let channel = |value: u16| match bit_depth {
+            16 => format!("{value:04x}"),
+            8 => format!("{value:02x}"),
+            depth => {
+                let max = (1u32 << depth) - 1;
+                format!("{:02x}", u32::from(value).min(max) * 255 / max)
+            }
+        };
+        match *self {
+            Self::PaletteIndex(_) => None,
+            Self::Gray(gray) => Some(format!("#{}", channel(gray).repeat(3))),
+            Self::Rgb(r, g, b) => Some(format!("#{}{}{}", channel(r), channel(g), channel(b))),
+        }
+    }
+}
+
+impl Display for Background {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PaletteIndex(index) => write!(f, "background palette index {index}"),
+            Self::Gray(gray) => write!(f, "background gray {gray}"),
+            Self::Rgb(r, g, b) => write!(f, "background rgb({r}, {g}, {b})"),
+        }
+    }
+}
+
+/// Pixel size or aspect ratio from a `pHYs` chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicalDimensions {
+    pub x_per_unit: u32,
+    pub y_per_unit: u32,
+    /// 1 for metres, 0 when only the aspect ratio is known
+    pub unit: u8,
+}
+
+impl TryFrom<&[u8]> for PhysicalDimensions {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        expect_len("pHYs", value, 9)?;
+        Ok(Self {
+            x_per_unit: u32::from_be_bytes(value[0..4].try_into().unwrap()),
+            y_per_unit: u32::from_be_bytes(value[4..8].try_into().unwrap()),
+            unit: value[8],
+        })
+    }
+}
+
+impl Display for PhysicalDimensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.unit {
+            1 => write!(
+                f,
+                "{}x{} pixels per metre ({:.0}x{:.0} dpi)",
+                self.x_per_unit,
+                self.y_per_unit,
+                self.x_per_unit as f64 * 0.0254,
+                self.y_per_unit as f64 * 0.0254
+            ),
+            0 => write!(f, "aspect ratio {}:{}", self.x_per_unit, self.y_per_unit),
+            unit => write!(
+                f,
+                "{}x{} pixels per unknown unit {unit}",
+                self.x_per_unit, self.y_per_unit
+            ),
+        }
+    }
+}
+
+/// Significant bits per channel from an `sBIT` chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignificantBits {
+    pub channels: Vec<(&'static str, u8)>,
+}
+
+impl SignificantBits {
+    pub fn parse(data: &[u8], color_type: u8) -> crate::Result<Self> {
+        let names: &[&'static str] = match color_type {
+            0 => &["gray"],
+            2 | 3 => &["red", "green", "blue"],
+            4 => &["gray", "alpha"],
+            6 => &["red", "green", "blue", "alpha"],
+            other => return Err(format!("Unknown color type {other}").into()),
+        };
+        expect_len("sBIT", data, names.len())?;
+        Ok(Self {
+            channels: names.iter().copied().zip(data.iter().copied()).collect(),
+        })
+    }
+}
+
+impl Display for SignificantBits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let channels = self
+            .channels
+            .iter()
+            .map(|(name, bits)| format!("{name} {bits}"))
+            .collect::<Vec<_>>();
+        write!(f, "significant bits: {}", channels.join(", "))
+    }
+}
+
+/// Image gamma from a `gAMA` chunk, stored as gamma times 100000
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gamma(pub u32);
+
+impl TryFrom<&[u8]> for Gamma {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        expect_len("gAMA", value, 4)?;
+        Ok(Self(u32::from_be_bytes(value.try_into().unwrap())))
+    }
+}
+
+impl Gamma {
+    pub fn from_value(gamma: f64) -> crate::Result<Self> {
+        let scaled = (gamma * 100000.0).round();
+        if !(1.0..=u32::MAX as f64).contains(&scaled) {
+            return Err(format!("Gamma must be positive, got {gamma}").into());
+        }
+        Ok(Self(scaled as u32))
+    }
+    pub fn value(&self) -> f64 {
+        self.0 as f64 / 100000.0
+    }
+    pub fn to_bytes(self) -> [u8; 4] {
+        self.0.to_be_bytes()
+    }
+}
+
+impl Display for Gamma {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "gamma: {:.5}", self.value())
+    }
+}
+
+/// A human-readable description of a chunk Print knows how to decode, or
+/// `None` for chunk types it shows as raw data only
+pub fn interpret(chunk_type: &str, data: &[u8], color_type: u8) -> Option<crate::Result<String>> {
+    let interpreted = match chunk_type {
+        "gAMA" => Gamma::try_from(data).map(|gama| gama.to_string()),
+        "bKGD" => Background::parse(data, color_type).map(|bkgd| bkgd.to_string()),
+        "pHYs" => PhysicalDimensions::try_from(data).map(|phys| phys.to_string()),
+        "sBIT" => SignificantBits::parse(data, color_type).map(|sbit| sbit.to_string()),
+        _ => return None,
+    };
+    Some(interpreted)
+}
+
+/// Turns a custom chunk's data into a human-readable description
+pub type Interpreter = Box<dyn Fn(&[u8]) -> String>;
+
+/// Chunk interpreters registered by library users, for chunk formats pngme
+/// doesn't know about. They take precedence over the built-in ones.
+///
+/// ```
+/// use pngme::ancillary::Interpreters;
+///
+/// let interpreters = Interpreters::new()
+///     .register(*b"ruSt", |data| format!("{} secret bytes", data.len()));
+/// ```
+#[derive(Default)]
+pub struct Interpreters(HashMap<[u8; 4], Interpreter>);
+
+impl Interpreters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn register(
+        mut self,
+        chunk_type: [u8; 4],
+        interpreter: impl Fn(&[u8]) -> String + 'static,
+    ) -> Self {
+        self.0.insert(chunk_type, Box::new(interpreter));
+        self
+    }
+    /// Like `interpret`, trying registered interpreters first. Built-in ones
+    /// need the image's color type, so only run when it is known.
+    pub fn interpret(
+        &self,
+        chunk_type: [u8; 4],
+        data: &[u8],
+        color_type: Option<u8>,
+    ) -> Option<crate::Result<String>> {
+        if let Some(interpreter) = self.0.get(&chunk_type) {
+            return Some(Ok(interpreter(data)));
+        }
+        interpret(std::str::from_utf8(&chunk_type).ok()?, data, color_type?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background() {
+        assert_eq!(
+            Background::parse(&[7], 3).unwrap(),
+            Background::PaletteIndex(7)
+        );
+        assert_eq!(
+            Background::parse(&[1, 0], 4).unwrap(),
+            Background::Gray(256)
+        );
+        assert_eq!(
+            Background::parse(&[0, 255, 0, 128, 0, 0], 6)
+                .unwrap()
+                .to_string(),
+            "background rgb(255, 128, 0)"
+        );
+        assert!(Background::parse(&[7], 2).is_err());
+    }
+
+    #[test]
+    fn test_background_hex() {
+        assert_eq!(Background::Rgb(255, 128, 0).hex(8).unwrap(), "#ff8000");
+        assert_eq!(
+            Background::Rgb(65535, 0, 4096).hex(16).unwrap(),
+            "#ffff00001000"
+        );
+        assert_eq!(Background::Gray(1).hex(1).unwrap(), "#ffffff");
+        assert_eq!(Background::Gray(5).hex(4).unwrap(), "#555555");
+        assert_eq!(Background::PaletteIndex(3).hex(8), None);
+    }
+
+    #[test]
+    fn test_physical_dimensions() {
+        let phys = PhysicalDimensions::try_from(&[0, 0, 11, 19, 0, 0, 11, 19, 1][..]).unwrap();
+        assert_eq!(phys.x_per_unit, 2835);
+        assert_eq!(phys.to_string(), "2835x2835 pixels per metre (72x72 dpi)");
+        let aspect = PhysicalDimensions::try_from(&[0, 0, 0, 2, 0, 0, 0, 1, 0][..]).unwrap();
+        assert_eq!(aspect.to_string(), "aspect ratio 2:1");
+        assert!(PhysicalDimensions::try_from(&[0; 8][..]).is_err());
+    }
+
+    #[test]
+    fn test_significant_bits() {
+        assert_eq!(
+            SignificantBits::parse(&[5, 6, 5], 2).unwrap().to_string(),
+            "significant bits: red 5, green 6, blue 5"
+        );
+        assert_eq!(
+            SignificantBits::parse(&[4, 1], 4).unwrap().channels,
+            [("gray", 4), ("alpha", 1)]
+        );
+        assert!(SignificantBits::parse(&[8, 8, 8], 6).is_err());
+    }
+
+    #[test]
+    fn test_gamma() {
+        let gamma = Gamma::try_from(&[0, 0, 177, 143][..]).unwrap();
+        assert_eq!(gamma, Gamma(45455));
+        assert_eq!(gamma.to_string(), "gamma: 0.45455");
+        assert_eq!(
+            Gamma::from_value(1.0 / 2.2).unwrap().to_bytes(),
+            [0, 0, 177, 143]
+        );
+        assert!(Gamma::from_value(0.0).is_err());
+        assert!(Gamma::from_value(-1.0).is_err());
+        assert!(Gamma::try_from(&[0, 1][..]).is_err());
+    }
+
+    #[test]
+    fn test_interpret() {
+        assert!(interpret("tEXt", b"a\0b", 2).is_none());
+        assert!(interpret("sBIT", &[8], 0).unwrap().is_ok());
+        assert!(interpret("bKGD", &[], 0).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_registered_interpreters() {
+        let interpreters = Interpreters::new()
+            .register(*b"ruSt", |data| format!("{} secret bytes", data.len()))
+            .register(*b"gAMA", |_| "overridden".to_string());
+        assert_eq!(
+            interpreters
+                .interpret(*b"ruSt", b"abc", None)
+                .unwrap()
+                .unwrap(),
+            "3 secret bytes"
+        );
+        assert_eq!(
+            interpreters
+                .interpret(*b"gAMA", &[0, 0, 177, 143], Some(2))
+                .unwrap()
+                .unwrap(),
+            "overridden"
+        );
+        assert!(Interpreters::new()
+            .interpret(*b"gAMA", &[0, 0, 177, 143], Some(2))
+            .is_some());
+        assert!(Interpreters::new()
+            .interpret(*b"gAMA", &[0, 0, 177, 143], None)
+            .is_none());
+    }
+}
@@ -1,33 +1,628 @@
 use std::path::PathBuf;
 
-use clap::{ColorChoice, Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug, Clone)]
 #[command(next_line_help = true)]
 pub struct Cli {
     #[command(subcommand)]
     pub commands: Commands,
+    /// Print only errors and the command's output, relying on the exit code
+    /// (also set by PNGME_QUIET)
+    #[arg(long, short, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Also print each chunk added or removed and where the result was
+    /// written (also set by PNGME_VERBOSE)
+    #[arg(long, short, global = true)]
+    pub verbose: bool,
+    /// When to use terminal colors, such as `palette --swatch`
+    #[arg(
+        long,
+        global = true,
+        value_name = "WHEN",
+        env = "PNGME_COLOR",
+        value_enum,
+        default_value_t = ColorChoice::Auto
+    )]
+    pub color: ColorChoice,
+    /// Make the change in memory and report it, without writing any file
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// Trace each chunk's offset, type and length on stderr while parsing
+    #[arg(
+        long,
+        global = true,
+        env = "PNGME_TRACE",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub trace: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only when stdout is a terminal and NO_COLOR is unset
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    Plain,
+    Json,
+}
+
+/// Byte order of UTF-16 code units
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    Le,
+    Be,
 }
 
 #[derive(Subcommand, Debug, Clone)]
+// Parsed once per run, so one big variant costs nothing
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Encodes PNG file
     Encode {
+        /// PNG to read, `-` for stdin (which needs an output file), or a
+        /// quoted glob such as `'images/*.png'` to encode every matching file
         file_path: PathBuf,
-        chunk_type: String,
-        message: String,
-        output_file: Option<String>,
+        /// Leave this and the message out with --append-from; the next
+        /// argument is then the output file
+        #[arg(required_unless_present = "append_from")]
+        chunk_type: Option<String>,
+        /// Leave this out with --message, --message-file or --data-file; the
+        /// next argument is then the output file
+        #[arg(required_unless_present_any = ["messages", "message_file", "data_file", "append_from"])]
+        message: Option<String>,
+        /// Where to write the result (`-` for stdout) instead of overwriting
+        /// the input
+        output_file: Option<PathBuf>,
+        /// Write each result into this directory under its own file name
+        /// instead of overwriting the input
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+        /// Overwrite the input when no output file or directory is given,
+        /// which is otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+        /// A line of the message; repeat it for several lines, which are
+        /// joined with a newline between each (none after the last)
+        #[arg(
+            long = "message",
+            short = 'm',
+            value_name = "LINE",
+            conflicts_with_all = ["message_file", "data_file"]
+        )]
+        messages: Vec<String>,
+        /// Read the message from this UTF-8 text file, exactly as it is
+        #[arg(long, value_name = "PATH", conflicts_with = "data_file")]
+        message_file: Option<PathBuf>,
+        /// Use the raw bytes of this file as the chunk data instead of a
+        /// message
+        #[arg(long, value_name = "PATH")]
+        data_file: Option<PathBuf>,
+        /// Copy every chunk of the --type from this PNG, bytes and CRC as
+        /// they are, instead of encoding a message
+        #[arg(
+            long,
+            value_name = "DONOR",
+            requires = "donor_type",
+            conflicts_with_all = [
+                "messages", "message_file", "data_file", "base64", "itxt", "text_keyword",
+                "with_checksum", "compress", "passphrase", "chunk_size", "index",
+                "random_position"
+            ]
+        )]
+        append_from: Option<PathBuf>,
+        /// The chunk type --append-from copies
+        #[arg(long = "type", value_name = "TYPE", requires = "append_from")]
+        donor_type: Option<String>,
+        /// The message is base64, for binary data
+        #[arg(long, conflicts_with = "data_file")]
+        base64: bool,
+        /// Print the chunk types before and after encoding
+        #[arg(long)]
+        show_changes: bool,
+        /// Store the message as international text under this keyword
+        /// (the chunk type must be iTXt)
+        #[arg(long, value_name = "KEYWORD")]
+        itxt: Option<String>,
+        /// Language tag for --itxt, e.g. "en" or "ja-JP"
+        #[arg(long, requires = "itxt", default_value = "")]
+        language: String,
+        /// Keyword translated into the --language, for --itxt
+        #[arg(long, requires = "itxt", default_value = "")]
+        translated_keyword: String,
+        /// Compress the --itxt text
+        #[arg(long, requires = "itxt")]
+        itxt_compressed: bool,
+        /// Store the message as `keyword\0message` under this keyword (the
+        /// chunk type must be tEXt)
+        #[arg(
+            long,
+            value_name = "KEYWORD",
+            conflicts_with_all = ["itxt", "with_checksum", "compress", "passphrase"]
+        )]
+        text_keyword: Option<String>,
+        /// Insert the chunk at a random spec-valid position between IHDR and
+        /// IEND instead of appending it
+        #[arg(long)]
+        random_position: bool,
+        /// Insert the chunk at this position in the chunk list instead of
+        /// before IEND. Index 0 puts it before IHDR, which makes the file
+        /// invalid
+        #[arg(long, value_name = "N", conflicts_with = "random_position")]
+        index: Option<usize>,
+        /// Seed for --random-position, to make the placement reproducible
+        #[arg(long, requires = "random_position")]
+        seed: Option<u64>,
+        /// Append a SHA-256 of the message for decode --verify-checksum
+        #[arg(long, conflicts_with = "itxt")]
+        with_checksum: bool,
+        /// Store the message zlib-compressed, for decode --decompress
+        #[arg(long, conflicts_with = "itxt")]
+        compress: bool,
+        /// Encrypt the message with this passphrase (ChaCha20-Poly1305), for
+        /// decode --passphrase
+        #[arg(long, conflicts_with_all = ["itxt", "compress"])]
+        passphrase: Option<String>,
+        /// Derive the encryption salt from the passphrase and the nonce from
+        /// the message, so encoding is reproducible; equal messages under one
+        /// passphrase then give equal chunks, so this is for testing only
+        #[arg(long, hide = true, requires = "passphrase")]
+        deterministic: bool,
+        /// Warn if the message looks like it contains a private key, access
+        /// token or password
+        #[arg(long)]
+        warn_secrets: bool,
+        /// With --warn-secrets, refuse to encode instead of only warning
+        #[arg(long, requires = "warn_secrets")]
+        strict: bool,
+        /// Accept chunks with a wrong CRC and write every chunk with a
+        /// freshly computed one; by default stored CRCs are kept as they are
+        #[arg(long)]
+        recompute_crcs: bool,
+        /// Fail instead of writing a chunk with more than N bytes of data
+        #[arg(long, value_name = "N")]
+        limit_chunk_size: Option<usize>,
+        /// Split data over N bytes into several chunks of at most N bytes,
+        /// each starting with a 12-byte sequence header, for decode --all
+        /// --join
+        #[arg(long, value_name = "N")]
+        chunk_size: Option<usize>,
     },
     /// Decods PNG file
     Decode {
+        file_path: PathBuf,
+        /// Chunk type, `TYPE:N` for its Nth occurrence (from 0) or `@N` for
+        /// the Nth chunk in the file
+        #[arg(value_name = "CHUNK", required_unless_present = "index")]
+        chunk_type: Option<String>,
+        /// Decode the Nth chunk in the file (from 0) whatever its type,
+        /// instead of passing a chunk type
+        #[arg(long, value_name = "N", conflicts_with_all = ["chunk_type", "all"])]
+        index: Option<usize>,
+        /// Match the chunk type ignoring the case of its letters, which by
+        /// the spec carry meaning, so `rust` finds `RuSt`
+        #[arg(long, short = 'i', conflicts_with = "index")]
+        case_insensitive: bool,
+        /// Only chunks of the type whose data is UTF-8 containing this text,
+        /// decoding the first of them (or, with --all, each one)
+        #[arg(long, value_name = "TEXT", conflicts_with = "index")]
+        contains: Option<String>,
+        /// Exit successfully with no output if the chunk is not found (also
+        /// set by PNGME_EXIT_ZERO_ON_NOT_FOUND or the config file)
+        #[arg(long, overrides_with = "no_exit_zero_on_not_found")]
+        exit_zero_on_not_found: bool,
+        /// Fail when the chunk is not found, even if the environment or the
+        /// config file says otherwise
+        #[arg(long, overrides_with = "exit_zero_on_not_found")]
+        no_exit_zero_on_not_found: bool,
+        /// Read a hex-encoded PNG from stdin (pass `-` as the file path)
+        #[arg(long)]
+        stdin_hex: bool,
+        /// Rename the file after decoding, replacing `{}` in TEMPLATE with the
+        /// decoded message
+        #[arg(long, value_name = "TEMPLATE")]
+        rename_to_content: Option<String>,
+        /// Decode as UTF-8, replacing invalid sequences instead of failing
+        #[arg(long)]
+        lossy: bool,
+        /// Decode as UTF-16 with the given byte order instead of UTF-8
+        #[arg(long, value_enum, value_name = "ORDER", conflicts_with = "lossy")]
+        utf16: Option<ByteOrder>,
+        /// Inflate a message stored with encode --compress
+        #[arg(long)]
+        decompress: bool,
+        /// Decrypt a message stored with encode --passphrase, failing if the
+        /// passphrase is wrong
+        #[arg(long, conflicts_with = "decompress")]
+        passphrase: Option<String>,
+        /// Check and strip the digest added by encode --with-checksum, failing
+        /// if it is missing or doesn't match
+        #[arg(long)]
+        verify_checksum: bool,
+        /// Escape bytes other than printable ASCII, newlines and tabs as `\xNN`
+        /// so the output is safe to show in a terminal
+        #[arg(long, conflicts_with_all = ["lossy", "utf16"])]
+        printable_only: bool,
+        /// Print the chunk data as base64, for binary data
+        #[arg(long, conflicts_with_all = ["lossy", "utf16", "printable_only"])]
+        base64: bool,
+        /// Copy the decoded message to the clipboard instead of printing it
+        #[arg(long)]
+        clipboard: bool,
+        /// Print every chunk of the type, one per line, instead of the first
+        #[arg(long, conflicts_with_all = ["rename_to_content", "clipboard"])]
+        all: bool,
+        /// With --all, put a message split by encode --chunk-size back
+        /// together and print it once
+        #[arg(long, requires = "all")]
+        join: bool,
+    },
+    /// Writes a chunk's raw data to a file, without decoding it
+    Extract {
+        file_path: PathBuf,
+        /// Chunk type, `TYPE:N` for its Nth occurrence (from 0) or `@N` for
+        /// the Nth chunk in the file
+        #[arg(value_name = "CHUNK")]
+        chunk_type: String,
+        /// Where to write the data, `-` for stdout
+        output_file: PathBuf,
+        /// Write every chunk of the type, each to OUTPUT_FILE with its
+        /// occurrence number before the extension (`out.0.bin`, `out.1.bin`)
+        #[arg(long)]
+        all: bool,
+        /// Copy the data of the one selected chunk (only the first of the
+        /// type unless CHUNK says `TYPE:N` or `@N`) from the input to the
+        /// output piece by piece instead of loading the whole file, for
+        /// payloads too big for memory. An output file is removed again if
+        /// the input turns out corrupt.
+        #[arg(long, conflicts_with = "all")]
+        spool: bool,
+    },
+    /// Replaces the data of the first chunk of a type, keeping its position
+    Replace {
         file_path: PathBuf,
         chunk_type: String,
+        message: String,
+        /// Add the chunk before IEND if the file has none of the type
+        #[arg(long)]
+        create: bool,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Hides a whole file in the PNG as stSh chunks, with its name and
+    /// length, for unstash to recover
+    Stash {
+        file_path: PathBuf,
+        /// The file to hide
+        stashed_file: PathBuf,
+        /// Store the contents in chunks of at most this many bytes
+        #[arg(long, value_name = "N", default_value_t = 65536)]
+        chunk_size: usize,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Recovers a file hidden by stash into a directory, under its original
+    /// name
+    Unstash {
+        file_path: PathBuf,
+        output_dir: PathBuf,
     },
     /// Removes chunk type from file
     Remove {
+        /// PNG to read, `-` for stdin, or a quoted glob such as
+        /// `'images/*.png'` to remove from every matching file
         file_path: PathBuf,
-        chunk_type: String,
+        /// Chunk type (every chunk of it), `TYPE:N` for its Nth occurrence
+        /// (from 0) or `@N` for the Nth chunk in the file
+        #[arg(value_name = "CHUNK", required_unless_present = "type_regex")]
+        chunk_type: Option<String>,
+        /// Remove only the first chunk of the type
+        #[arg(long, conflicts_with = "type_regex")]
+        first: bool,
+        /// Match the chunk type ignoring the case of its letters, which by
+        /// the spec carry meaning, so `rust` finds `RuSt`
+        #[arg(long, short = 'i', conflicts_with = "type_regex")]
+        case_insensitive: bool,
+        /// Remove every chunk whose type matches this regular expression
+        #[arg(long, conflicts_with = "chunk_type")]
+        type_regex: Option<String>,
+        /// Allow --type-regex to remove critical chunks
+        #[arg(long, requires = "type_regex")]
+        allow_critical: bool,
+        /// Confirm removing a standard critical chunk (IHDR, PLTE, IDAT or
+        /// IEND), which leaves a broken PNG
+        #[arg(long, short = 'y', conflicts_with = "type_regex")]
+        yes: bool,
+        /// Print the chunk types before and after removing
+        #[arg(long)]
+        show_changes: bool,
+        /// Accept chunks with a wrong CRC and write every chunk with a
+        /// freshly computed one; by default stored CRCs are kept as they are
+        #[arg(long)]
+        recompute_crcs: bool,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Write each result into this directory under its own file name
+        /// instead of overwriting the input
+        #[arg(long, value_name = "DIR", conflicts_with = "output_file")]
+        output_dir: Option<PathBuf>,
+        /// Overwrite the input when no output file or directory is given,
+        /// which is otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Renames chunk types in bulk from a file of `old_type new_type` lines
+    Rename {
+        file_path: PathBuf,
+        #[arg(long, value_name = "MAPPING_FILE")]
+        chunk_type_file: PathBuf,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
     },
     /// Prints PNG header and chunks
-    Print { file_path: PathBuf },
+    Print {
+        file_path: PathBuf,
+        /// Skip over corrupt regions instead of failing (also set by
+        /// PNGME_LENIENT or the config file)
+        #[arg(long, overrides_with = "no_lenient")]
+        lenient: bool,
+        /// Fail on corrupt regions, even if the environment or the config
+        /// file says to be lenient
+        #[arg(long, overrides_with = "lenient")]
+        no_lenient: bool,
+        /// Read a hex-encoded PNG from stdin (pass `-` as the file path)
+        #[arg(long)]
+        stdin_hex: bool,
+        /// Seek past IDAT data instead of loading it
+        #[arg(long, conflicts_with_all = ["lenient", "stdin_hex"])]
+        skip_idat: bool,
+        /// Print the file as a base64 `data:` URI instead
+        #[arg(long, conflicts_with = "skip_idat")]
+        data_uri: bool,
+        /// Truncate chunk data previews to this many columns (defaults to the
+        /// terminal width when stdout is a terminal, from `COLUMNS` if set)
+        #[arg(long)]
+        width: Option<usize>,
+        /// On a parse error, show the offset and surrounding bytes where
+        /// parsing stopped
+        #[arg(long, conflicts_with_all = ["lenient", "skip_idat"])]
+        diagnose: bool,
+        /// Show only the last N chunks, plus IEND
+        #[arg(long, value_name = "N")]
+        tail: Option<usize>,
+        /// Describe each standard chunk type
+        #[arg(long)]
+        explain: bool,
+        /// Also list the Adler-32 of each chunk's data
+        #[arg(long, conflicts_with = "data_uri")]
+        adler: bool,
+        /// The input is a bare chunk stream without the PNG signature
+        #[arg(
+            long,
+            alias = "no-signature",
+            conflicts_with_all = ["lenient", "skip_idat", "diagnose"]
+        )]
+        assume_png: bool,
+        /// Print the chunks as a JSON array instead, with base64 data
+        #[arg(long, conflicts_with_all = ["data_uri", "explain", "adler"])]
+        json: bool,
+        /// Show bKGD colors as `rgb(r, g, b)` instead of `#rrggbb`
+        #[arg(long)]
+        decimal_colors: bool,
+        /// Hexdump all of each chunk's data instead of the first 64 bytes
+        #[arg(long, conflicts_with_all = ["data_uri", "json"])]
+        full: bool,
+        /// Show only chunks of this type; repeat to show several types
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            visible_alias = "type-filter",
+            conflicts_with = "data_uri"
+        )]
+        type_filter: Vec<String>,
+    },
+    /// Lists each chunk's index, type, kind (critical or ancillary),
+    /// visibility (public or private), whether it is safe to copy, offset,
+    /// length and CRC, then the number of chunks
+    List {
+        file_path: PathBuf,
+        /// List the last chunk first
+        #[arg(long)]
+        reverse: bool,
+        /// Show at most this many chunks (after --reverse)
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Instead of the rows, draw each chunk's length as a bar, in file
+        /// order (ASCII when the locale isn't UTF-8)
+        #[arg(long, conflicts_with_all = ["reverse", "limit"])]
+        sparkline: bool,
+        /// The input is a bare chunk stream without the PNG signature
+        #[arg(long, alias = "no-signature")]
+        assume_png: bool,
+        /// Print the chunks as a JSON array instead, with base64 data
+        #[arg(long, conflicts_with = "sparkline")]
+        json: bool,
+        /// List only chunks of this type; repeat to list several types
+        #[arg(long = "type", value_name = "TYPE", visible_alias = "type-filter")]
+        type_filter: Vec<String>,
+    },
+    /// Prints how many chunks of the type the file has
+    Count {
+        file_path: PathBuf,
+        chunk_type: String,
+        /// Match the chunk type ignoring the case of its letters, which by
+        /// the spec carry meaning, so `rust` finds `RuSt`
+        #[arg(long, short = 'i')]
+        case_insensitive: bool,
+    },
+    /// Creates or updates the gAMA chunk, e.g. 0.45455 for sRGB-like gamma
+    SetGamma {
+        file_path: PathBuf,
+        gamma: f64,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Concatenates the data of all IDAT chunks, in order, into a single IDAT
+    MergeIdat {
+        file_path: PathBuf,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Removes every ancillary chunk, including text, color profiles and any
+    /// hidden messages, keeping only the critical ones
+    Clean {
+        file_path: PathBuf,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Shrinks the file without touching the image: merges IDAT chunks,
+    /// removes duplicate ancillary chunks and empty chunks other than IEND,
+    /// and recomputes every CRC
+    Optimize {
+        file_path: PathBuf,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Splits the IDAT data into IDAT chunks of at most --size bytes each
+    SplitIdat {
+        file_path: PathBuf,
+        /// Largest IDAT chunk to write, in bytes
+        #[arg(long, required_unless_present = "limit_chunk_size")]
+        size: Option<usize>,
+        /// Make sure no chunk holds more than N bytes: IDAT pieces are kept
+        /// within it and any other chunk over it is an error
+        #[arg(long, value_name = "N")]
+        limit_chunk_size: Option<usize>,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Rewrites every chunk with a CRC computed from its type and data, e.g.
+    /// after editing chunk data by hand
+    FixCrc {
+        file_path: PathBuf,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Lists only private (custom) chunks, with a preview of their data
+    Custom { file_path: PathBuf },
+    /// Stores a SHA-256 of all other chunks in a seal chunk
+    Seal {
+        file_path: PathBuf,
+        /// Write the result here (`-` for stdout) instead of overwriting the
+        /// input
+        #[arg(long)]
+        output_file: Option<PathBuf>,
+        /// Overwrite the input when no output file is given, which is
+        /// otherwise refused
+        #[arg(long, short = 'f', visible_alias = "force")]
+        in_place: bool,
+    },
+    /// Checks that the seal chunk matches the rest of the file
+    VerifySeal { file_path: PathBuf },
+    /// Checks the file against PNG structural rules, printing every problem
+    /// found or OK
+    Verify {
+        file_path: PathBuf,
+        /// Fail on warnings such as unknown critical chunks, which decoders
+        /// can't render
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Lists the keyword and value of every text chunk
+    Text {
+        file_path: PathBuf,
+        #[arg(long, value_enum, default_value_t = TextFormat::Plain)]
+        format: TextFormat,
+    },
+    /// Checks whether two PNGs decode to the same pixels, ignoring metadata
+    /// and how the pixels are stored (color type, bit depth, palette or
+    /// interlacing)
+    CompareImage { a: PathBuf, b: PathBuf },
+    /// Lists the ancillary (metadata) chunks added, removed or changed from
+    /// one PNG to another, ignoring pixel data
+    CompareMeta { a: PathBuf, b: PathBuf },
+    /// Lines up the chunks of two PNGs and lists those added, removed,
+    /// moved or changed, exiting with 1 if there are any
+    Diff { a: PathBuf, b: PathBuf },
+    /// Round-trips a message through a generated PNG
+    SelfTest,
+    /// Prints the palette of an indexed image as hex colors
+    Palette {
+        file_path: PathBuf,
+        /// Show a color swatch next to each entry (needs a true-color
+        /// terminal, and is left out when --color turns colors off)
+        #[arg(long)]
+        swatch: bool,
+        /// Show colors as `rgb(r, g, b)` instead of `#rrggbb`
+        #[arg(long)]
+        decimal_colors: bool,
+    },
+    /// Guesses whether the file holds an embedded message. This is a
+    /// heuristic based on nonstandard chunks, not proof either way
+    Detect { file_path: PathBuf },
+    /// Prints every ancillary chunk whose data is valid UTF-8 without control
+    /// characters as `type: text`, skipping binary ones, to find messages of
+    /// any type
+    Scan { file_path: PathBuf },
 }
@@ -1,86 +1,320 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    io::{ErrorKind, Read, Write},
+    sync::OnceLock,
+};
 
-use crate::chunk_type::ChunkType;
+use crate::{chunk_type::ChunkType, encoding::base64_encode, json::Json, zlib, PngError};
 
 use crc::{Crc, CRC_32_ISO_HDLC};
 
+pub(crate) const CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// Marks chunk data written by `encode --compress`: these bytes, then the
+/// message as a zlib stream
+pub const COMPRESSED_MAGIC: &[u8; 4] = b"PMZL";
+
+/// Starts each piece of data split by `encode --chunk-size`, followed by the
+/// piece's index and the number of pieces (4 bytes each, big-endian)
+pub const PIECE_MAGIC: &[u8; 4] = b"PMSQ";
+const PIECE_HEADER_LEN: usize = 12;
+
 #[derive(Clone)]
 pub struct Chunk {
     length: u32,
     chunk_type: ChunkType,
     chunk_data: Vec<u8>,
-    crc: u32,
+    /// The stored CRC, or empty until `crc` first computes it from the type
+    /// and data. Anything that changes either resets it.
+    crc: OnceLock<u32>,
+    data_skipped: bool,
 }
 
 impl TryFrom<&[u8]> for Chunk {
     type Error = super::Error;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let too_short = || PngError::TooShort {
+            expected: 12,
+            found: value.len(),
+        };
         let mut iter = value.iter();
 
-        let length = u32::from_be_bytes(
-            iter.by_ref()
-                .take(4)
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap(),
-        );
+        let length_bytes: [u8; 4] = iter.by_ref().take(4).copied().collect::<Vec<_>>()[..]
+            .try_into()
+            .map_err(|_| too_short())?;
+        let length = u32::from_be_bytes(length_bytes);
         // println!("{:?}", &value[0..4]);
 
-        let chunk_type_bytes: [u8; 4] =
-            iter.by_ref().take(4).copied().collect::<Vec<u8>>()[..].try_into()?;
-        let chunk_type: ChunkType = chunk_type_bytes.try_into()?;
+        let chunk_type_bytes: [u8; 4] = iter.by_ref().take(4).copied().collect::<Vec<u8>>()[..]
+            .try_into()
+            .map_err(|_| too_short())?;
+        let chunk_type: ChunkType = chunk_type_bytes
+            .try_into()
+            .map_err(|e: PngError| PngError::Malformed(e.to_string()))?;
+        let remaining = value.len() - 8;
+        // Checked, since the sum can overflow usize on 32-bit targets
+        if (length as usize)
+            .checked_add(4)
+            .is_none_or(|needed| remaining < needed)
+        {
+            return Err(PngError::Malformed(format!(
+                "declared data length {length} exceeds remaining bytes {remaining}"
+            )));
+        }
         let data = iter
             .by_ref()
             .take(length as usize)
             .copied()
             .collect::<Vec<u8>>();
         // eprintln!("{} <|< {} <|< {}", value.len(), length, 4 + 4 + data.len());
-        let remaining_bytes = iter.copied().take(4) .collect::<Vec<u8>>();
-        let original_crc =
-            u32::from_be_bytes(remaining_bytes.try_into().unwrap());
-        let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-        let mut digest = crc.digest();
-        digest.update(&chunk_type_bytes);
-        digest.update(&data);
-        let crc = digest.finalize();
+        let remaining_bytes = iter.copied().take(4).collect::<Vec<u8>>();
+        let original_crc = u32::from_be_bytes(remaining_bytes.try_into().unwrap());
+        let crc = Self::calculate_crc(&chunk_type, &data);
         if crc == original_crc {
-            Ok(Self {
-                length,
-                chunk_type,
-                chunk_data: data,
-                crc,
-            })
+            Self::with_stored_crc(chunk_type, data, crc)
         } else {
-            Err("Invalid chunk CRC".into())
+            Err(PngError::CrcMismatch {
+                expected: crc,
+                found: original_crc,
+            })
         }
     }
 }
 
+/// Chunks are equal when their type, declared length, data and CRC are, so a
+/// chunk with a bad stored CRC differs from the same chunk with a good one
+impl PartialEq for Chunk {
+    fn eq(&self, other: &Self) -> bool {
+        self.chunk_type == other.chunk_type
+            && self.length == other.length
+            && self.chunk_data == other.chunk_data
+            && self.crc() == other.crc()
+    }
+}
+
+impl Eq for Chunk {}
+
+impl Hash for Chunk {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.chunk_type.hash(state);
+        self.length.hash(state);
+        self.chunk_data.hash(state);
+        self.crc().hash(state);
+    }
+}
+
 impl Display for Chunk {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display_showing(Self::PREVIEW_BYTES).fmt(f)
+    }
+}
+
+/// Displays a `Chunk` like its `Display` impl, hexdumping up to a given
+/// number of bytes of data; see `Chunk::display_showing`
+pub struct ChunkDisplay<'a> {
+    chunk: &'a Chunk,
+    shown: usize,
+}
+
+impl Display for ChunkDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let chunk = self.chunk;
         writeln!(f, "Chunk:",)?;
-        writeln!(f, "    length: {}", self.length)?;
-        writeln!(f, "    chunk_type: {}", self.chunk_type)?;
-        writeln!(f, "    chunk_data: {:?}", self.chunk_data)?;
-        writeln!(f, "    crc: {}", self.crc)
+        writeln!(f, "    length: {}", chunk.length)?;
+        writeln!(f, "    chunk_type: {}", chunk.chunk_type)?;
+        writeln!(f, "    properties: {}", chunk.chunk_type.properties())?;
+        if chunk.data_skipped {
+            writeln!(f, "    chunk_data: [data not loaded]")?;
+        } else {
+            let data = &chunk.chunk_data;
+            let shown = data.len().min(self.shown);
+            let mut lines = vec![format!("    chunk_data: {} bytes", data.len())];
+            lines.extend(hexdump(&data[..shown]));
+            if shown < data.len() {
+                lines.push(format!("    ... {} more bytes", data.len() - shown));
+            }
+            for line in lines {
+                match f.width() {
+                    Some(width) if line.chars().count() > width => {
+                        let truncated = line
+                            .chars()
+                            .take(width.saturating_sub(1))
+                            .collect::<String>();
+                        writeln!(f, "{truncated}…")?;
+                    }
+                    _ => writeln!(f, "{line}")?,
+                }
+            }
+        }
+        writeln!(f, "    crc: {}", chunk.crc())
     }
 }
 
+/// `offset  hex  ascii` lines of 16 bytes each, with unprintable bytes shown
+/// as `.`
+fn hexdump(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    data.chunks(16).enumerate().map(|(row, bytes)| {
+        let hex = bytes
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x20..=0x7e => byte as char,
+                _ => '.',
+            })
+            .collect::<String>();
+        format!("    {:08x}  {hex:<47}  {ascii}", row * 16)
+    })
+}
+
 impl Chunk {
+    /// How many bytes of data `Display` hexdumps
+    pub const PREVIEW_BYTES: usize = 64;
+
+    /// A chunk whose CRC is computed from the type and data when first
+    /// needed. Panics if `data` is too long for the 4-byte length field; use
+    /// `try_new` for data that might be.
     pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Chunk {
-        let length = data.len().try_into().unwrap();
-        let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-        let mut digest = crc.digest();
-        digest.update(&chunk_type.bytes());
-        digest.update(&data);
-        let crc = digest.finalize();
+        Self::try_new(chunk_type, data).unwrap()
+    }
+    /// Like `new`, but fails instead of panicking when `data` is over
+    /// `u32::MAX` bytes
+    pub fn try_new(chunk_type: ChunkType, data: Vec<u8>) -> crate::Result<Chunk> {
+        let length = Self::checked_length(data.len())?;
+        Ok(Self::new_unchecked(chunk_type, data, length))
+    }
+    fn checked_length(length: usize) -> crate::Result<u32> {
+        u32::try_from(length).map_err(|_| {
+            format!(
+                "Chunk data of {length} bytes is over the limit of {} bytes",
+                u32::MAX
+            )
+            .into()
+        })
+    }
+    /// A chunk holding `message` zlib-compressed behind `COMPRESSED_MAGIC`
+    pub fn compressed(chunk_type: ChunkType, message: &[u8]) -> Chunk {
+        let mut data = COMPRESSED_MAGIC.to_vec();
+        data.extend(zlib::compress(message));
+        Self::new(chunk_type, data)
+    }
+    /// The message of a chunk made by `compressed`
+    pub fn decompressed_data(&self) -> crate::Result<Vec<u8>> {
+        let compressed = self
+            .chunk_data
+            .strip_prefix(COMPRESSED_MAGIC)
+            .ok_or("Chunk data was not compressed by `encode --compress`")?;
+        zlib::decompress(compressed)
+    }
+    /// Splits `data` into chunks of at most `chunk_size` bytes, each a piece
+    /// headed by `PIECE_MAGIC`. Data that already fits is one chunk with no
+    /// header.
+    pub fn split_data(
+        chunk_type: ChunkType,
+        data: &[u8],
+        chunk_size: usize,
+    ) -> crate::Result<Vec<Chunk>> {
+        if data.len() <= chunk_size {
+            return Ok(vec![Self::new(chunk_type, data.to_vec())]);
+        }
+        if chunk_size <= PIECE_HEADER_LEN {
+            return Err(format!(
+                "Chunk size must be over {PIECE_HEADER_LEN} bytes to fit the piece header"
+            )
+            .into());
+        }
+        let pieces = data.chunks(chunk_size - PIECE_HEADER_LEN);
+        let count = u32::try_from(pieces.len()).map_err(|_| "Too many pieces")?;
+        Ok(pieces
+            .zip(0u32..)
+            .map(|(piece, index)| {
+                let mut data = PIECE_MAGIC.to_vec();
+                data.extend(index.to_be_bytes());
+                data.extend(count.to_be_bytes());
+                data.extend(piece);
+                Self::new(chunk_type, data)
+            })
+            .collect())
+    }
+    /// Puts data split by `split_data` back together, ordering the pieces by
+    /// their index. Chunks without piece headers are joined in the order
+    /// given.
+    pub fn join_data(chunks: &[&Chunk]) -> crate::Result<Vec<u8>> {
+        let headers = chunks
+            .iter()
+            .map(|chunk| {
+                let header = chunk.chunk_data.get(..PIECE_HEADER_LEN)?;
+                header.starts_with(PIECE_MAGIC).then(|| {
+                    let index = u32::from_be_bytes(header[4..8].try_into().unwrap());
+                    let count = u32::from_be_bytes(header[8..].try_into().unwrap());
+                    (index, count, &chunk.chunk_data[PIECE_HEADER_LEN..])
+                })
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(mut pieces) = headers else {
+            if chunks
+                .iter()
+                .any(|chunk| chunk.chunk_data.starts_with(PIECE_MAGIC))
+            {
+                return Err("Can't join split pieces with chunks that aren't pieces".into());
+            }
+            return Ok(chunks
+                .iter()
+                .flat_map(|chunk| chunk.chunk_data.iter().copied())
+                .collect());
+        };
+        pieces.sort_by_key(|&(index, _, _)| index);
+        let count = pieces.len();
+        for (expected, &(index, total, _)) in (0u32..).zip(&pieces) {
+            if total as usize != count || index != expected {
+                return Err(format!(
+                    "Split message is incomplete: found {count} pieces, expected {total}"
+                )
+                .into());
+            }
+        }
+        Ok(pieces
+            .into_iter()
+            .flat_map(|(_, _, piece)| piece.iter().copied())
+            .collect())
+    }
+    /// A chunk holding a CRC read from a file, which may not match its data.
+    /// Fails like `try_new` when `data` is over `u32::MAX` bytes.
+    pub fn with_stored_crc(chunk_type: ChunkType, data: Vec<u8>, crc: u32) -> crate::Result<Chunk> {
+        Ok(Self {
+            length: Self::checked_length(data.len())?,
+            chunk_type,
+            chunk_data: data,
+            crc: OnceLock::from(crc),
+            data_skipped: false,
+        })
+    }
+    /// A chunk that declares `length` regardless of how much data it holds.
+    /// When they differ, the serialized chunk is invalid and will desync any
+    /// parser reading it; this exists for crafting test inputs for decoders.
+    pub fn new_unchecked(chunk_type: ChunkType, data: Vec<u8>, length: u32) -> Chunk {
         Self {
             length,
             chunk_type,
             chunk_data: data,
-            crc,
+            crc: OnceLock::new(),
+            data_skipped: false,
+        }
+    }
+    /// A chunk whose data was not read, keeping only its declared length and
+    /// stored CRC. Serializing it will not reproduce the original bytes.
+    pub fn skipped(chunk_type: ChunkType, length: u32, crc: u32) -> Chunk {
+        Self {
+            length,
+            chunk_type,
+            chunk_data: Vec::new(),
+            crc: OnceLock::from(crc),
+            data_skipped: true,
         }
     }
     pub fn length(&self) -> u32 {
@@ -93,27 +327,207 @@ impl Chunk {
         &self.chunk_data
     }
     pub fn crc(&self) -> u32 {
-        self.crc
+        *self.crc.get_or_init(|| self.computed_crc())
+    }
+    /// Changes the chunk type, updating the CRC to match
+    pub fn set_chunk_type(&mut self, chunk_type: ChunkType) {
+        self.chunk_type = chunk_type;
+        self.crc = OnceLock::new();
+    }
+    /// Replaces the data, updating the length and CRC to match
+    pub fn set_data(&mut self, data: Vec<u8>) {
+        *self = Self::new(self.chunk_type, data);
+    }
+    /// Replaces the stored CRC with one computed from the type and data,
+    /// returning whether it was wrong. Skipped chunks are left alone.
+    pub fn recompute_crc(&mut self) -> bool {
+        if self.data_skipped {
+            return false;
+        }
+        let stored = std::mem::take(&mut self.crc).into_inner();
+        stored.is_some_and(|stored| stored != self.crc())
+    }
+    /// Whether the chunk's type is `chunk_type`, comparing letters without
+    /// regard to ASCII case when `ignore_case`
+    pub fn has_type(&self, chunk_type: &str, ignore_case: bool) -> bool {
+        let bytes = self.chunk_type.bytes();
+        match ignore_case {
+            true => bytes.eq_ignore_ascii_case(chunk_type.as_bytes()),
+            false => bytes == chunk_type.as_bytes(),
+        }
+    }
+    /// Splits `keyword\0value` text chunk data at the first NUL
+    pub fn split_keyword(&self) -> Option<(&[u8], &[u8])> {
+        let nul = self.chunk_data.iter().position(|&byte| byte == 0)?;
+        Some((&self.chunk_data[..nul], &self.chunk_data[nul + 1..]))
+    }
+    /// Adler-32 of the chunk data, as used inside zlib streams
+    pub fn adler32(&self) -> u32 {
+        zlib::adler32(&self.chunk_data)
+    }
+    /// The chunk as a JSON object, with its data base64-encoded (`null` when
+    /// it wasn't loaded)
+    pub fn to_json(&self) -> Json {
+        let data = match self.data_skipped {
+            true => Json::Null,
+            false => Json::from(base64_encode(&self.chunk_data)),
+        };
+        Json::Object(vec![
+            ("type".to_string(), Json::from(self.chunk_type.to_string())),
+            ("length".to_string(), Json::from(self.length)),
+            ("crc".to_string(), Json::from(self.crc())),
+            (
+                "critical".to_string(),
+                Json::from(self.chunk_type.is_critical()),
+            ),
+            ("data".to_string(), data),
+        ])
+    }
+    /// Displays the chunk with up to `shown` bytes of data instead of
+    /// `PREVIEW_BYTES`; a width truncates lines as for `Display`
+    pub fn display_showing(&self, shown: usize) -> ChunkDisplay<'_> {
+        ChunkDisplay { chunk: self, shown }
+    }
+    pub fn is_data_skipped(&self) -> bool {
+        self.data_skipped
+    }
+    pub fn calculate_crc(chunk_type: &ChunkType, data: &[u8]) -> u32 {
+        let mut digest = CRC.digest();
+        digest.update(&chunk_type.bytes());
+        digest.update(data);
+        digest.finalize()
+    }
+    /// The CRC the type and data should have, which differs from `crc` for
+    /// a chunk loaded with a bad stored CRC
+    pub fn computed_crc(&self) -> u32 {
+        Self::calculate_crc(&self.chunk_type, &self.chunk_data)
+    }
+    pub fn is_crc_valid(&self) -> bool {
+        self.crc() == self.computed_crc()
     }
     pub fn data_as_string(&self) -> crate::Result<String> {
-        let data = self
-            .chunk_data
-            .iter()
-            .map(|byte| *byte as char)
-            .collect::<String>();
-        Ok(data)
+        Ok(String::from_utf8(self.chunk_data.clone())?)
+    }
+    pub fn data_as_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.chunk_data).into_owned()
     }
+    /// Fails if the chunk holds more than `limit` bytes of data
+    pub fn check_size_limit(&self, limit: usize) -> crate::Result<()> {
+        if self.length as usize > limit {
+            return Err(format!(
+                "{} chunk holds {} bytes of data, over the chunk size limit of {limit}",
+                self.chunk_type, self.length
+            )
+            .into());
+        }
+        Ok(())
+    }
+    /// Writes the same bytes as `as_bytes` without copying the data first
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> crate::Result<()> {
+        writer.write_all(&self.length.to_be_bytes())?;
+        writer.write_all(&self.chunk_type.bytes())?;
+        writer.write_all(&self.chunk_data)?;
+        writer.write_all(&self.crc().to_be_bytes())?;
+        Ok(())
+    }
+    /// How many bytes `write_to` writes: the data plus 4 bytes each of
+    /// length, type and CRC
+    pub fn serialized_len(&self) -> usize {
+        12 + self.chunk_data.len()
+    }
+    /// The serialized chunk, as `write_to` writes it
     pub fn as_bytes(&self) -> Vec<u8> {
-        let length = self.length.to_be_bytes();
-        let chunk_type = self.chunk_type.bytes();
-        let data = self.chunk_data.clone().into_iter();
-        let crc = self.crc.to_be_bytes();
-        length
-            .into_iter()
-            .chain(chunk_type)
-            .chain(data)
-            .chain(crc)
-            .collect()
+        let mut bytes = Vec::with_capacity(self.serialized_len());
+        self.write_to(&mut bytes)
+            .expect("writing to a Vec never fails");
+        bytes
+    }
+}
+
+/// Reads serialized chunks (with no PNG signature) from a stream one at a
+/// time, so only the chunk being read is held in memory. Yields an error for
+/// a truncated chunk or a bad CRC, and nothing after that or after IEND.
+pub struct ChunkReader<R> {
+    reader: R,
+    offset: usize,
+    done: bool,
+}
+
+impl<R: Read> ChunkReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            offset: 0,
+            done: false,
+        }
+    }
+    /// How many bytes have been read so far
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+    fn read_chunk(&mut self) -> crate::Result<Option<Chunk>> {
+        let start = self.offset;
+        let truncated = |e: std::io::Error| match e.kind() {
+            ErrorKind::UnexpectedEof => {
+                PngError::Malformed(format!("Truncated chunk at offset {start}"))
+            }
+            _ => PngError::Io(e),
+        };
+        // Running out of input before a chunk starts is the normal end
+        let mut length = [0; 4];
+        let mut filled = 0;
+        while filled < 4 {
+            match self.reader.read(&mut length[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => {
+                    return Err(PngError::Malformed(format!(
+                        "Truncated chunk at offset {start}"
+                    )))
+                }
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let length = u32::from_be_bytes(length);
+        let mut chunk_type = [0; 4];
+        self.reader.read_exact(&mut chunk_type).map_err(truncated)?;
+        let chunk_type = ChunkType::try_from(chunk_type)?;
+        let mut data = Vec::new();
+        self.reader
+            .by_ref()
+            .take(length.into())
+            .read_to_end(&mut data)?;
+        if data.len() != length as usize {
+            return Err(PngError::Malformed(format!(
+                "Truncated chunk at offset {start}"
+            )));
+        }
+        let mut crc = [0; 4];
+        self.reader.read_exact(&mut crc).map_err(truncated)?;
+        let crc = u32::from_be_bytes(crc);
+        let expected = Chunk::calculate_crc(&chunk_type, &data);
+        if crc != expected {
+            return Err(PngError::CrcMismatch {
+                expected,
+                found: crc,
+            });
+        }
+        self.offset += 12 + data.len();
+        Ok(Some(Chunk::with_stored_crc(chunk_type, data, crc)?))
+    }
+}
+
+impl<R: Read> Iterator for ChunkReader<R> {
+    type Item = crate::Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_chunk().transpose();
+        self.done = !matches!(&result, Some(Ok(chunk)) if chunk.chunk_type().to_string() != "IEND");
+        result
     }
 }
 
@@ -172,12 +586,28 @@ mod tests {
         assert_eq!(chunk_string, expected_chunk_string);
     }
 
+    #[test]
+    fn test_chunk_string_lossy() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let chunk = Chunk::new(chunk_type, b"caf\xc3\xa9 \xff\xfe!".to_vec());
+        assert_eq!(chunk.data_as_string_lossy(), "café \u{fffd}\u{fffd}!");
+    }
+
     #[test]
     fn test_chunk_crc() {
         let chunk = testing_chunk();
         assert_eq!(chunk.crc(), 2882656334);
     }
 
+    #[test]
+    fn test_stored_and_computed_crc() {
+        let chunk = testing_chunk();
+        let stored = Chunk::with_stored_crc(*chunk.chunk_type(), chunk.data().to_vec(), 1).unwrap();
+        assert_eq!(stored.crc(), 1);
+        assert_eq!(stored.computed_crc(), chunk.crc());
+        assert!(!stored.is_crc_valid());
+    }
+
     #[test]
     fn test_valid_chunk_from_bytes() {
         let data_length: u32 = 42;
@@ -223,7 +653,288 @@ mod tests {
 
         let chunk = Chunk::try_from(chunk_data.as_ref());
 
-        assert!(chunk.is_err());
+        assert!(matches!(
+            chunk,
+            Err(PngError::CrcMismatch {
+                expected: 2882656334,
+                found: 2882656333
+            })
+        ));
+    }
+
+    #[test]
+    fn test_skipped_chunk_display() {
+        let chunk_type = ChunkType::from_str("IDAT").unwrap();
+        let skipped = Chunk::skipped(chunk_type, 42, 0);
+        assert!(skipped.to_string().contains("[data not loaded]"));
+
+        let empty = Chunk::new(chunk_type, Vec::new());
+        assert!(empty.to_string().contains("chunk_data: 0 bytes\n"));
+    }
+
+    #[test]
+    fn test_display_hexdumps_data() {
+        let shown = testing_chunk().to_string();
+        assert!(shown.contains("chunk_data: 42 bytes\n"));
+        assert!(shown.contains(
+            "    00000000  54 68 69 73 20 69 73 20 77 68 65 72 65 20 79 6f  This is where yo\n"
+        ));
+        assert!(shown.contains("    00000020  65 20 77 69 6c 6c 20 62 65 21"));
+        assert!(shown.contains("  e will be!\n"));
+
+        let data = (0..=255).collect::<Vec<u8>>();
+        let large = Chunk::new(ChunkType::from_str("IDAT").unwrap(), data);
+        let truncated = large.to_string();
+        assert!(truncated.contains("    00000030  "));
+        assert!(!truncated.contains("    00000040  "));
+        assert!(truncated.contains("    ... 192 more bytes\n"));
+        let full = large.display_showing(usize::MAX).to_string();
+        assert!(full.contains("    000000f0  f0 f1"));
+        assert!(!full.contains("more bytes"));
+    }
+
+    #[test]
+    fn test_new_unchecked_emits_declared_length() {
+        let chunk =
+            Chunk::new_unchecked(ChunkType::from_str("ruSt").unwrap(), b"four".to_vec(), 1000);
+        let bytes = chunk.as_bytes();
+        assert_eq!(bytes[..4], 1000u32.to_be_bytes());
+        assert_eq!(bytes.len(), 4 + 4 + 4 + 4);
+        assert_eq!(chunk.data(), b"four");
+    }
+
+    #[test]
+    fn test_set_data() {
+        let mut chunk = testing_chunk();
+        chunk.set_data(b"new data".to_vec());
+        assert_eq!(chunk.length(), 8);
+        assert_eq!(chunk.data(), b"new data");
+        assert!(chunk.is_crc_valid());
+    }
+
+    #[test]
+    fn test_chunk_equality() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let message = b"This is where your secret message will be!".to_vec();
+        let parsed = testing_chunk();
+        assert!(parsed == Chunk::try_from(&parsed.as_bytes()[..]).unwrap());
+        assert!(parsed == Chunk::new(chunk_type, message.clone()));
+        assert!(parsed != Chunk::new(chunk_type, b"another message".to_vec()));
+        assert!(parsed != Chunk::with_stored_crc(chunk_type, message, 0).unwrap());
+
+        // The CRC cache only ever fills in the value the hash is made from
+        #[allow(clippy::mutable_key_type)]
+        let set = std::collections::HashSet::from([parsed.clone(), testing_chunk()]);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_crc_computed_lazily() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let message = b"This is where your secret message will be!".to_vec();
+        let chunk = Chunk::new(chunk_type, message.clone());
+        assert!(chunk.crc.get().is_none());
+        assert_eq!(chunk.crc(), Chunk::calculate_crc(&chunk_type, &message));
+        assert_eq!(chunk.crc(), testing_chunk().crc());
+        assert!(chunk.crc.get().is_some());
+    }
+
+    #[test]
+    fn test_crc_follows_data_changes() {
+        let mut chunk = testing_chunk();
+        assert_eq!(chunk.crc(), 2882656334);
+        chunk.set_data(b"another message".to_vec());
+        let eager = Chunk::calculate_crc(chunk.chunk_type(), b"another message");
+        assert_eq!(chunk.crc(), eager);
+        assert!(chunk.is_crc_valid());
+
+        let mut stored = Chunk::with_stored_crc(*chunk.chunk_type(), b"x".to_vec(), 1).unwrap();
+        assert_eq!(stored.crc(), 1);
+        stored.set_chunk_type(ChunkType::from_str("ruSt").unwrap());
+        assert!(stored.is_crc_valid());
+    }
+
+    #[test]
+    fn test_set_chunk_type() {
+        let mut chunk = testing_chunk();
+        chunk.set_chunk_type(ChunkType::from_str("ruSt").unwrap());
+        assert_eq!(chunk.chunk_type().to_string(), "ruSt");
+        assert!(chunk.is_crc_valid());
+    }
+
+    #[test]
+    fn test_split_keyword() {
+        let chunk = Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Title\0a\0b".to_vec(),
+        );
+        assert_eq!(chunk.split_keyword(), Some((&b"Title"[..], &b"a\0b"[..])));
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"Title\0".to_vec());
+        assert_eq!(chunk.split_keyword(), Some((&b"Title"[..], &b""[..])));
+    }
+
+    #[test]
+    fn test_split_keyword_without_nul() {
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"Title".to_vec());
+        assert_eq!(chunk.split_keyword(), None);
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let message = r#"{"secret": "meet at the usual place"}"#.repeat(50);
+        let chunk = Chunk::compressed(ChunkType::from_str("ruSt").unwrap(), message.as_bytes());
+        assert!((chunk.length() as usize) < message.len());
+        assert_eq!(chunk.decompressed_data().unwrap(), message.as_bytes());
+        let plain = Chunk::new(ChunkType::from_str("ruSt").unwrap(), message.into_bytes());
+        assert!(plain.decompressed_data().is_err());
+    }
+
+    #[test]
+    fn test_truncated_chunk_is_error() {
+        for len in [0, 3, 7] {
+            let bytes = vec![0u8; len];
+            let err = Chunk::try_from(&bytes[..]).err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("chunk too short: expected 12 bytes, found {len}")
+            );
+            assert!(matches!(err, PngError::TooShort { found, .. } if found == len));
+        }
+    }
+
+    #[test]
+    fn test_chunk_missing_crc_is_error() {
+        let bytes = testing_chunk().as_bytes();
+        let err = Chunk::try_from(&bytes[..bytes.len() - 1]).err().unwrap();
+        assert!(err.to_string().starts_with("declared data length"));
+    }
+
+    #[test]
+    fn test_declared_length_beyond_buffer() {
+        let mut bytes = testing_chunk().as_bytes();
+        bytes[..4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let remaining = bytes.len() - 8;
+        let err = Chunk::try_from(&bytes[..]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "declared data length {} exceeds remaining bytes {remaining}",
+                u32::MAX
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_to_matches_as_bytes() {
+        let chunk = testing_chunk();
+        let mut written = Vec::new();
+        chunk.write_to(&mut written).unwrap();
+        let mut expected = 42u32.to_be_bytes().to_vec();
+        expected.extend(b"RuSt");
+        expected.extend(b"This is where your secret message will be!");
+        expected.extend(chunk.crc().to_be_bytes());
+        assert_eq!(written, expected);
+        assert_eq!(chunk.as_bytes(), expected);
+        assert_eq!(chunk.serialized_len(), expected.len());
+    }
+
+    #[test]
+    fn test_try_new_rejects_oversized_data() {
+        assert_eq!(Chunk::checked_length(42).unwrap(), 42);
+        assert_eq!(Chunk::checked_length(u32::MAX as usize).unwrap(), u32::MAX);
+        if let Some(length) = (u32::MAX as usize).checked_add(1) {
+            let err = Chunk::checked_length(length).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Chunk data of 4294967296 bytes is over the limit of 4294967295 bytes"
+            );
+        }
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        assert!(
+            Chunk::try_new(chunk_type, b"hi".to_vec()).unwrap()
+                == Chunk::new(chunk_type, b"hi".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_utf8_round_trip() {
+        let message = "café 🎉";
+        let chunk = Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            message.as_bytes().to_vec(),
+        );
+        let chunk = Chunk::try_from(&chunk.as_bytes()[..]).unwrap();
+        assert_eq!(chunk.data_as_string().unwrap(), message);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_error() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![b'a', 0xff, 0xfe]);
+        assert!(chunk.data_as_string().is_err());
+    }
+
+    #[test]
+    fn test_chunk_adler32() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"Wikipedia".to_vec());
+        assert_eq!(chunk.adler32(), 0x11e60398);
+    }
+
+    #[test]
+    fn test_split_and_join_data() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let message = b"a message several times larger than the chunk size".repeat(3);
+        let pieces = Chunk::split_data(chunk_type, &message, 20).unwrap();
+        assert_eq!(pieces.len(), message.len().div_ceil(8));
+        assert!(pieces.iter().all(|piece| piece.length() <= 20));
+        let mut shuffled = pieces.iter().collect::<Vec<_>>();
+        shuffled.reverse();
+        assert_eq!(Chunk::join_data(&shuffled).unwrap(), message);
+        assert!(Chunk::join_data(&shuffled[1..]).is_err());
+
+        let whole = Chunk::split_data(chunk_type, b"short", 20).unwrap();
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].data(), b"short");
+        assert_eq!(
+            Chunk::join_data(&[&whole[0], &whole[0]]).unwrap(),
+            b"shortshort"
+        );
+        assert!(Chunk::join_data(&[&whole[0], &pieces[0]]).is_err());
+        assert!(Chunk::split_data(chunk_type, &message, 12).is_err());
+    }
+
+    #[test]
+    fn test_check_size_limit() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0; 10]);
+        assert!(chunk.check_size_limit(10).is_ok());
+        assert_eq!(
+            chunk.check_size_limit(9).unwrap_err().to_string(),
+            "ruSt chunk holds 10 bytes of data, over the chunk size limit of 9"
+        );
+    }
+
+    #[test]
+    fn test_chunk_to_json() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec());
+        assert_eq!(
+            chunk.to_json().to_string(),
+            format!(
+                r#"{{"type":"ruSt","length":2,"crc":{},"critical":false,"data":"aGk="}}"#,
+                chunk.crc()
+            )
+        );
+    }
+
+    #[test]
+    fn test_chunk_display_width() {
+        let chunk = testing_chunk();
+        let full = chunk.to_string();
+        let data_line = full.lines().nth(5).unwrap();
+        assert!(data_line.chars().count() > 30);
+        let capped = format!("{chunk:30}");
+        let capped_line = capped.lines().nth(5).unwrap();
+        assert_eq!(capped_line.chars().count(), 30);
+        assert!(capped_line.ends_with('…'));
+        assert_eq!(format!("{chunk:1000}"), full);
     }
 
     #[test]
@@ -1,13 +1,28 @@
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct ChunkType([u8; 4]);
 
+/// Why the bytes aren't a chunk type, naming the first one that isn't an
+/// ASCII letter
+fn invalid_byte(value: &[u8; 4]) -> Option<String> {
+    let index = value.iter().position(|b| !b.is_ascii_alphabetic())?;
+    let byte = value[index];
+    let shown = match byte {
+        b' '..=b'~' => format!("'{}'", byte as char),
+        _ => format!("{byte:#04x}"),
+    };
+    Some(format!("byte {index} {shown} is not an ASCII letter"))
+}
+
 impl TryFrom<[u8; 4]> for ChunkType {
     type Error = super::Error;
 
     fn try_from(value: [u8; 4]) -> Result<Self, Self::Error> {
-        Ok(Self(value))
+        match invalid_byte(&value) {
+            Some(reason) => Err(format!("invalid chunk type: {reason}").into()),
+            None => Ok(Self(value)),
+        }
     }
 }
 
@@ -15,12 +30,22 @@ impl FromStr for ChunkType {
     type Err = super::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.chars().all(char::is_alphabetic) {
-            let value = s.chars().map(|c| c as u8).collect::<Vec<u8>>();
-            let value: [u8; 4] = value.try_into().unwrap();
-            Ok(Self(value))
-        } else {
-            Err("Invalid ChunkType".into())
+        if let Some((index, c)) = s.char_indices().find(|(_, c)| !c.is_ascii()) {
+            return Err(format!(
+                "invalid chunk type {s:?}: byte {index} '{c}' is not an ASCII letter"
+            )
+            .into());
+        }
+        let bytes = <[u8; 4]>::try_from(s.as_bytes()).map_err(|_| {
+            let problem = if s.len() < 4 { "too short" } else { "too long" };
+            format!(
+                "invalid chunk type {s:?}: {problem}, expected 4 bytes but found {}",
+                s.len()
+            )
+        })?;
+        match invalid_byte(&bytes) {
+            Some(reason) => Err(format!("invalid chunk type {s:?}: {reason}").into()),
+            None => Ok(Self(bytes)),
         }
     }
 }
@@ -54,6 +79,69 @@ impl ChunkType {
     pub fn is_safe_to_copy(&self) -> bool {
         self.0[3].is_ascii_lowercase()
     }
+    /// The property bits spelled out, e.g. "ancillary, private, safe-to-copy",
+    /// ending in "reserved bit set" when the third letter is lowercase
+    pub fn properties(&self) -> String {
+        let mut properties = vec![
+            if self.is_critical() {
+                "critical"
+            } else {
+                "ancillary"
+            },
+            if self.is_public() {
+                "public"
+            } else {
+                "private"
+            },
+            if self.is_safe_to_copy() {
+                "safe-to-copy"
+            } else {
+                "unsafe-to-copy"
+            },
+        ];
+        if !self.is_reserved_bit_valid() {
+            properties.push("reserved bit set");
+        }
+        properties.join(", ")
+    }
+    /// A one-line description of a standard chunk type
+    pub fn description(&self) -> &'static str {
+        self.standard_description()
+            .unwrap_or("custom/private chunk")
+    }
+    /// Whether this is one of the chunk types `description` knows about
+    pub fn is_standard(&self) -> bool {
+        self.standard_description().is_some()
+    }
+    fn standard_description(&self) -> Option<&'static str> {
+        let description = match &self.0 {
+            b"IHDR" => "image header: dimensions, bit depth, color type",
+            b"PLTE" => "palette: the colors of an indexed image",
+            b"IDAT" => "image data: zlib-compressed, filtered scanlines",
+            b"IEND" => "image trailer: marks the end of the file",
+            b"tRNS" => "transparency: alpha for palette entries or a transparent color",
+            b"cHRM" => "chromaticities of the primaries and white point",
+            b"gAMA" => "image gamma",
+            b"iCCP" => "embedded ICC color profile",
+            b"sBIT" => "significant bits per channel in the original image",
+            b"sRGB" => "image uses the sRGB color space, with a rendering intent",
+            b"cICP" => "coding-independent code points for video color spaces",
+            b"tEXt" => "Latin-1 text with a keyword",
+            b"zTXt" => "compressed Latin-1 text with a keyword",
+            b"iTXt" => "international UTF-8 text with a keyword and language",
+            b"bKGD" => "default background color",
+            b"hIST" => "palette histogram: approximate usage of each palette entry",
+            b"pHYs" => "physical pixel dimensions or aspect ratio",
+            b"sPLT" => "suggested palette for displays with limited colors",
+            b"eXIf" => "Exif metadata",
+            b"tIME" => "time of last modification",
+            b"acTL" => "animation control: frame and play counts",
+            b"fcTL" => "frame control: size, position and timing of an animation frame",
+            b"fdAT" => "frame data: image data of an animation frame",
+            _ => return None,
+        };
+        Some(description)
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +150,18 @@ mod tests {
     use std::convert::TryFrom;
     use std::str::FromStr;
 
+    #[test]
+    fn test_chunk_type_description() {
+        let description = |s: &str| ChunkType::from_str(s).unwrap().description();
+        assert_eq!(
+            description("IHDR"),
+            "image header: dimensions, bit depth, color type"
+        );
+        assert_eq!(description("RuSt"), "custom/private chunk");
+        assert!(ChunkType::from_str("tIME").unwrap().is_standard());
+        assert!(!ChunkType::from_str("RuSt").unwrap().is_standard());
+    }
+
     #[test]
     pub fn test_chunk_type_from_bytes() {
         let expected = [82, 117, 83, 116];
@@ -140,6 +240,60 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    pub fn test_property_bits_of_known_types() {
+        let rust = ChunkType::from_str("ruSt").unwrap();
+        assert!(!rust.is_critical());
+        assert!(!rust.is_public());
+        assert!(rust.is_reserved_bit_valid());
+        assert!(rust.is_safe_to_copy());
+        assert_eq!(rust.properties(), "ancillary, private, safe-to-copy");
+
+        let upper = ChunkType::from_str("RUST").unwrap();
+        assert!(upper.is_critical());
+        assert!(upper.is_public());
+        assert!(upper.is_reserved_bit_valid());
+        assert!(!upper.is_safe_to_copy());
+
+        let ihdr = ChunkType::from_str("IHDR").unwrap();
+        assert!(ihdr.is_critical());
+        assert!(ihdr.is_public());
+        assert!(ihdr.is_reserved_bit_valid());
+        assert!(!ihdr.is_safe_to_copy());
+        assert_eq!(ihdr.properties(), "critical, public, unsafe-to-copy");
+
+        assert_eq!(
+            ChunkType::from_str("rust").unwrap().properties(),
+            "ancillary, private, safe-to-copy, reserved bit set"
+        );
+    }
+
+    #[test]
+    pub fn test_chunk_type_errors_name_the_problem() {
+        let error = |s: &str| ChunkType::from_str(s).unwrap_err().to_string();
+        assert_eq!(
+            error("rus"),
+            "invalid chunk type \"rus\": too short, expected 4 bytes but found 3"
+        );
+        assert_eq!(
+            error("rusty"),
+            "invalid chunk type \"rusty\": too long, expected 4 bytes but found 5"
+        );
+        assert_eq!(
+            error("ru5t"),
+            "invalid chunk type \"ru5t\": byte 2 '5' is not an ASCII letter"
+        );
+        assert_eq!(
+            error("rüs"),
+            "invalid chunk type \"rüs\": byte 1 'ü' is not an ASCII letter"
+        );
+        assert!(error("").contains("too short"));
+        assert_eq!(
+            ChunkType::try_from(*b"ru\0t").unwrap_err().to_string(),
+            "invalid chunk type: byte 2 0x00 is not an ASCII letter"
+        );
+    }
+
     #[test]
     pub fn test_chunk_type_string() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -0,0 +1,257 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::args::{Cli, Commands};
+
+/// Defaults read from `$XDG_CONFIG_HOME/pngme/config.toml`
+/// (or `~/.config/pngme/config.toml`).
+///
+/// Only a small subset of TOML is understood: `[section]` headers,
+/// `key = value` pairs and `#` comments. Keys inside a section are looked up
+/// as `section.key`, e.g. `[print]` followed by `lenient = true`.
+///
+/// Precedence is CLI > env > config file > built-in defaults.
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("pngme").join("config.toml"))
+    }
+    pub fn load() -> crate::Result<Config> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::parse(&fs::read_to_string(path)?),
+            _ => Ok(Config::default()),
+        }
+    }
+    pub fn parse(s: &str) -> crate::Result<Config> {
+        let mut values = HashMap::new();
+        let mut section = String::new();
+        for (number, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = format!("{}.", name.trim());
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("config line {}: expected `key = value`", number + 1))?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            values.insert(format!("{section}{}", key.trim()), value.to_string());
+        }
+        Ok(Self { values })
+    }
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+    pub fn flag(&self, key: &str) -> crate::Result<bool> {
+        match self.get(key) {
+            None | Some("false") => Ok(false),
+            Some("true") => Ok(true),
+            Some(other) => {
+                Err(format!("config key `{key}` must be true or false, got `{other}`").into())
+            }
+        }
+    }
+    /// Resolves an on/off option: `--flag` or `--no-flag` on the command
+    /// line, else the environment variable, else the config file
+    fn resolve_flag(
+        &self,
+        (on, off): (bool, bool),
+        key: &str,
+        (name, value): (&str, Option<String>),
+    ) -> crate::Result<bool> {
+        if on || off {
+            return Ok(on);
+        }
+        match env_flag((name, value))? {
+            Some(value) => Ok(value),
+            None => self.flag(key),
+        }
+    }
+    /// Fills in options the command line left unset from the environment
+    /// and then the config file
+    pub fn apply(&self, cli: &mut Cli) -> crate::Result<()> {
+        self.apply_with_env(cli, |name| std::env::var(name).ok())
+    }
+    fn apply_with_env(
+        &self,
+        cli: &mut Cli,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> crate::Result<()> {
+        let env = |name| (name, env(name));
+        // Set after parsing rather than by clap, which would treat a variable
+        // as if the flag had been passed and reject `PNGME_QUIET=true -v`
+        if !cli.quiet && !cli.verbose {
+            cli.quiet = env_flag(env("PNGME_QUIET"))?.unwrap_or(false);
+            cli.verbose = env_flag(env("PNGME_VERBOSE"))?.unwrap_or(false);
+            if cli.quiet && cli.verbose {
+                return Err("PNGME_QUIET and PNGME_VERBOSE can't both be true".into());
+            }
+        }
+        match &mut cli.commands {
+            Commands::Decode {
+                exit_zero_on_not_found,
+                no_exit_zero_on_not_found,
+                ..
+            } => {
+                *exit_zero_on_not_found = self.resolve_flag(
+                    (*exit_zero_on_not_found, *no_exit_zero_on_not_found),
+                    "decode.exit_zero_on_not_found",
+                    env("PNGME_EXIT_ZERO_ON_NOT_FOUND"),
+                )?
+            }
+            Commands::Print {
+                lenient,
+                no_lenient,
+                ..
+            } => {
+                *lenient = self.resolve_flag(
+                    (*lenient, *no_lenient),
+                    "print.lenient",
+                    env("PNGME_LENIENT"),
+                )?
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// An on/off environment variable's value, if it is set
+fn env_flag((name, value): (&str, Option<String>)) -> crate::Result<Option<bool>> {
+    match value.as_deref() {
+        Some("true" | "1") => Ok(Some(true)),
+        Some("false" | "0" | "") => Ok(Some(false)),
+        Some(other) => Err(format!("{name} must be true or false, got `{other}`").into()),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_parse_sections_and_comments() {
+        let config = Config::parse(
+            "# defaults\n[print]\nlenient = true # always\n\n[decode]\nexit_zero_on_not_found = false\n",
+        )
+        .unwrap();
+        assert!(config.flag("print.lenient").unwrap());
+        assert!(!config.flag("decode.exit_zero_on_not_found").unwrap());
+        assert!(!config.flag("missing").unwrap());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_line() {
+        assert!(Config::parse("[print]\nlenient\n").is_err());
+    }
+
+    #[test]
+    fn test_flag_rejects_non_boolean() {
+        let config = Config::parse("[print]\nlenient = \"yes\"\n").unwrap();
+        assert!(config.flag("print.lenient").is_err());
+    }
+
+    #[test]
+    fn test_apply_sets_defaults() {
+        let config = Config::parse("[print]\nlenient = true\n").unwrap();
+        let mut cli = Cli::parse_from(["pngme", "print", "image.png"]);
+        config.apply(&mut cli).unwrap();
+        assert!(matches!(
+            cli.commands,
+            Commands::Print { lenient: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let config =
+            Config::parse("[print]\nlenient = true\n[decode]\nexit_zero_on_not_found = true\n")
+                .unwrap();
+        let mut cli = Cli::parse_from(["pngme", "print", "image.png", "--no-lenient"]);
+        config.apply(&mut cli).unwrap();
+        assert!(matches!(
+            cli.commands,
+            Commands::Print { lenient: false, .. }
+        ));
+        let mut cli = Cli::parse_from([
+            "pngme",
+            "decode",
+            "image.png",
+            "ruSt",
+            "--no-exit-zero-on-not-found",
+        ]);
+        config.apply(&mut cli).unwrap();
+        assert!(matches!(
+            cli.commands,
+            Commands::Decode {
+                exit_zero_on_not_found: false,
+                ..
+            }
+        ));
+        let mut cli = Cli::parse_from(["pngme", "print", "image.png", "--no-lenient", "--lenient"]);
+        Config::default().apply(&mut cli).unwrap();
+        assert!(matches!(
+            cli.commands,
+            Commands::Print { lenient: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_quiet_and_verbose_from_the_environment() {
+        let apply = |args: &[&str], quiet: &str, verbose: &str| {
+            let mut cli =
+                Cli::parse_from([&["pngme", "count", "a.png", "IDAT"][..], args].concat());
+            let value = |name: &str| match name {
+                "PNGME_QUIET" => Some(quiet.to_string()),
+                "PNGME_VERBOSE" => Some(verbose.to_string()),
+                _ => None,
+            };
+            Config::default()
+                .apply_with_env(&mut cli, value)
+                .map(|()| (cli.quiet, cli.verbose))
+        };
+        assert_eq!(apply(&[], "true", "false").unwrap(), (true, false));
+        assert_eq!(apply(&[], "0", "1").unwrap(), (false, true));
+        assert_eq!(apply(&["-v"], "true", "false").unwrap(), (false, true));
+        assert_eq!(apply(&["-q"], "false", "true").unwrap(), (true, false));
+        assert!(apply(&[], "true", "true").is_err());
+    }
+
+    #[test]
+    fn test_environment_overrides_config_and_yields_to_command_line() {
+        let config = Config::parse("[print]\nlenient = true\n").unwrap();
+        let lenient = |args: &[&str], env: Option<&str>| {
+            let mut cli = Cli::parse_from([&["pngme", "print", "image.png"][..], args].concat());
+            config
+                .apply_with_env(&mut cli, |name| {
+                    env.filter(|_| name == "PNGME_LENIENT").map(str::to_string)
+                })
+                .unwrap();
+            matches!(cli.commands, Commands::Print { lenient: true, .. })
+        };
+        assert!(!lenient(&[], Some("false")));
+        assert!(lenient(&[], Some("1")));
+        assert!(lenient(&["--lenient"], Some("false")));
+        assert!(!lenient(&["--no-lenient"], Some("true")));
+        let mut cli = Cli::parse_from(["pngme", "print", "image.png"]);
+        assert!(config
+            .apply_with_env(&mut cli, |_| Some("maybe".to_string()))
+            .is_err());
+    }
+}
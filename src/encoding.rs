@@ -0,0 +1,158 @@
+/// Decodes a hex string, ignoring any whitespace between digits
+pub fn hex_decode(s: &str) -> crate::Result<Vec<u8>> {
+    let digits = s
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).ok_or(format!("Invalid hex digit '{c}'")))
+        .collect::<Result<Vec<u32>, String>>()?;
+    if digits.len() % 2 != 0 {
+        return Err("Hex input has an odd number of digits".into());
+    }
+    Ok(digits
+        .chunks_exact(2)
+        .map(|pair| (pair[0] * 16 + pair[1]) as u8)
+        .collect())
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with `=` padding
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let n = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes standard base64, ignoring whitespace and accepting missing padding
+pub fn base64_decode(s: &str) -> crate::Result<Vec<u8>> {
+    let values = s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    let values = values.trim_end_matches('=');
+    let values = values
+        .bytes()
+        .map(|c| {
+            BASE64_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .map(|v| v as u32)
+                .ok_or(format!("Invalid base64 character '{}'", c as char))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+    if values.len() % 4 == 1 {
+        return Err("Truncated base64 input".into());
+    }
+    let mut out = Vec::with_capacity(values.len() * 3 / 4);
+    for group in values.chunks(4) {
+        let n = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &v)| n | v << (18 - 6 * i));
+        for i in 0..group.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// Decodes UTF-16 code units of the given byte order, failing on an odd byte
+/// count or an unpaired surrogate
+pub fn utf16_decode(bytes: &[u8], big_endian: bool) -> crate::Result<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(format!("UTF-16 data has an odd length of {} bytes", bytes.len()).into());
+    }
+    let units = bytes.chunks(2).map(|pair| {
+        let pair = [pair[0], pair[1]];
+        if big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        }
+    });
+    char::decode_utf16(units)
+        .enumerate()
+        .map(|(i, c)| {
+            c.map_err(|e| {
+                format!(
+                    "Invalid UTF-16: unpaired surrogate {:#06x} near byte {}",
+                    e.unpaired_surrogate(),
+                    i * 2
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_decode() {
+        assert_eq!(
+            hex_decode("89504e47").unwrap(),
+            vec![0x89, 0x50, 0x4e, 0x47]
+        );
+        assert_eq!(
+            hex_decode("DEADbeef").unwrap(),
+            vec![0xde, 0xad, 0xbe, 0xef]
+        );
+    }
+
+    #[test]
+    fn test_hex_decode_ignores_whitespace() {
+        assert_eq!(
+            hex_decode(" 89 50\n4e\t47\r\n").unwrap(),
+            vec![0x89, 0x50, 0x4e, 0x47]
+        );
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(&[0xfb, 0xff]), "+/8=");
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(base64_decode("Zm9vYmE=").unwrap(), b"fooba");
+        assert_eq!(base64_decode("Zm9vYg").unwrap(), b"foob");
+        assert_eq!(base64_decode("Zm9v\nYmFy").unwrap(), b"foobar");
+        assert!(base64_decode("Zm9v!").is_err());
+        assert!(base64_decode("Zm9vY").is_err());
+    }
+
+    #[test]
+    fn test_hex_decode_rejects_invalid() {
+        assert!(hex_decode("8950g0").is_err());
+        assert!(hex_decode("895").is_err());
+    }
+
+    #[test]
+    fn test_utf16_decode() {
+        assert_eq!(utf16_decode(b"h\0i\0", false).unwrap(), "hi");
+        assert_eq!(utf16_decode(b"\0h\0i", true).unwrap(), "hi");
+        assert_eq!(
+            utf16_decode(&[0x3d, 0xd8, 0x00, 0xde], false).unwrap(),
+            "\u{1f600}"
+        );
+        assert!(utf16_decode(b"abc", false).is_err());
+        assert!(utf16_decode(&[0x00, 0xd8, 0x41, 0x00], false).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! The error type returned throughout the library.
+
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum PngError {
+    /// The input doesn't start with the 8-byte PNG signature
+    InvalidSignature,
+    /// A chunk's stored CRC doesn't match the one computed from its type and
+    /// data
+    CrcMismatch {
+        expected: u32,
+        found: u32,
+    },
+    /// No chunk of the given type, or at the given position
+    ChunkNotFound(String),
+    /// Fewer bytes than the 12 of a chunk's length, type and CRC
+    TooShort {
+        expected: usize,
+        found: usize,
+    },
+    /// The file ends in the middle of a chunk, or a chunk's length or type
+    /// can't be right
+    Malformed(String),
+    Io(std::io::Error),
+    /// Anything else, described by its message
+    Other(String),
+}
+
+impl Display for PngError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "not a PNG file: invalid signature"),
+            Self::CrcMismatch { expected, found } => write!(
+                f,
+                "Invalid chunk CRC: expected {expected:#010x}, found {found:#010x}"
+            ),
+            Self::ChunkNotFound(chunk) => write!(f, "Chunk not found: {chunk}"),
+            Self::TooShort { expected, found } => write!(
+                f,
+                "chunk too short: expected {expected} bytes, found {found}"
+            ),
+            Self::Malformed(message) => write!(f, "{message}"),
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Other(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for PngError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PngError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<String> for PngError {
+    fn from(value: String) -> Self {
+        Self::Other(value)
+    }
+}
+
+impl From<&str> for PngError {
+    fn from(value: &str) -> Self {
+        Self::Other(value.to_string())
+    }
+}
+
+impl From<std::string::FromUtf8Error> for PngError {
+    fn from(value: std::string::FromUtf8Error) -> Self {
+        Self::Other(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let crc = PngError::CrcMismatch {
+            expected: 0x1234,
+            found: 0xabcdef,
+        };
+        assert_eq!(
+            crc.to_string(),
+            "Invalid chunk CRC: expected 0x00001234, found 0x00abcdef"
+        );
+        assert_eq!(
+            PngError::ChunkNotFound("ruSt".to_string()).to_string(),
+            "Chunk not found: ruSt"
+        );
+        assert_eq!(PngError::from("anything").to_string(), "anything");
+    }
+
+    #[test]
+    fn test_io_source() {
+        use std::error::Error;
+
+        let io = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof");
+        assert!(PngError::from(io).source().is_some());
+        assert!(PngError::InvalidSignature.source().is_none());
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interlace {
+    None,
+    Adam7,
+    Unknown(u8),
+}
+
+impl From<u8> for Interlace {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::Adam7,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl Display for Interlace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Adam7 => write!(f, "Adam7"),
+            Self::Unknown(value) => write!(f, "unknown ({value})"),
+        }
+    }
+}
+
+/// The fields of an IHDR chunk's 13-byte payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IhdrInfo {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace: Interlace,
+}
+
+impl TryFrom<&[u8]> for IhdrInfo {
+    type Error = super::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let value: &[u8; 13] = value
+            .try_into()
+            .map_err(|_| format!("IHDR must be 13 bytes, found {}", value.len()))?;
+        Ok(Self {
+            width: u32::from_be_bytes(value[0..4].try_into().unwrap()),
+            height: u32::from_be_bytes(value[4..8].try_into().unwrap()),
+            bit_depth: value[8],
+            color_type: value[9],
+            compression_method: value[10],
+            filter_method: value[11],
+            interlace: value[12].into(),
+        })
+    }
+}
+
+impl IhdrInfo {
+    /// Checks the constraints the spec puts on the header: a non-zero width
+    /// and height, and a bit depth allowed for the color type
+    pub fn validate(&self) -> super::Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!(
+                "IHDR dimensions must be non-zero, found {}x{}",
+                self.width, self.height
+            )
+            .into());
+        }
+        let allowed: &[u8] = match self.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            other => {
+                return Err(format!("IHDR color type {other} is not a valid color type").into())
+            }
+        };
+        if !allowed.contains(&self.bit_depth) {
+            return Err(format!(
+                "IHDR bit depth {} is not allowed with color type {}, expected one of {allowed:?}",
+                self.bit_depth, self.color_type
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl Display for IhdrInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{}, bit depth {}, color type {}, interlace: {}",
+            self.width, self.height, self.bit_depth, self.color_type, self.interlace
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ihdr_bytes(interlace: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(50u32.to_be_bytes());
+        bytes.extend(40u32.to_be_bytes());
+        bytes.extend([8, 6, 0, 0, interlace]);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_ihdr() {
+        let info = IhdrInfo::try_from(&ihdr_bytes(0)[..]).unwrap();
+        assert_eq!(info.width, 50);
+        assert_eq!(info.height, 40);
+        assert_eq!(info.bit_depth, 8);
+        assert_eq!(info.color_type, 6);
+    }
+
+    #[test]
+    fn test_non_interlaced() {
+        let info = IhdrInfo::try_from(&ihdr_bytes(0)[..]).unwrap();
+        assert_eq!(info.interlace, Interlace::None);
+        assert_eq!(info.interlace.to_string(), "none");
+    }
+
+    #[test]
+    fn test_adam7_interlaced() {
+        let info = IhdrInfo::try_from(&ihdr_bytes(1)[..]).unwrap();
+        assert_eq!(info.interlace, Interlace::Adam7);
+        assert_eq!(info.interlace.to_string(), "Adam7");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(IhdrInfo::try_from(&ihdr_bytes(0)[..])
+            .unwrap()
+            .validate()
+            .is_ok());
+
+        let mut zero_width = ihdr_bytes(0);
+        zero_width[..4].copy_from_slice(&0u32.to_be_bytes());
+        let info = IhdrInfo::try_from(&zero_width[..]).unwrap();
+        assert_eq!(
+            info.validate().unwrap_err().to_string(),
+            "IHDR dimensions must be non-zero, found 0x40"
+        );
+
+        let mut indexed_16 = ihdr_bytes(0);
+        indexed_16[8..10].copy_from_slice(&[16, 3]);
+        let info = IhdrInfo::try_from(&indexed_16[..]).unwrap();
+        assert_eq!(
+            info.validate().unwrap_err().to_string(),
+            "IHDR bit depth 16 is not allowed with color type 3, expected one of [1, 2, 4, 8]"
+        );
+
+        let mut bad_color_type = ihdr_bytes(0);
+        bad_color_type[9] = 5;
+        assert!(IhdrInfo::try_from(&bad_color_type[..])
+            .unwrap()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_wrong_length() {
+        assert!(IhdrInfo::try_from(&[0u8; 12][..]).is_err());
+    }
+}
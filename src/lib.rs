@@ -0,0 +1,45 @@
+//! Reading, editing and writing PNG files chunk by chunk.
+//!
+//! ```
+//! use std::str::FromStr;
+//!
+//! use pngme::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+//!
+//! # fn main() -> pngme::Result<()> {
+//! let mut png = Png::default();
+//! let ihdr = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+//! png.append_chunk(Chunk::new(ChunkType::from_str("IHDR")?, ihdr.to_vec()));
+//! png.append_chunk(Chunk::new(ChunkType::from_str("ruSt")?, b"hidden".to_vec()));
+//! png.append_chunk(Chunk::new(ChunkType::from_str("IEND")?, Vec::new()));
+//!
+//! let bytes = png.as_bytes();
+//! let decoded = Png::try_from(&bytes[..])?;
+//! let message = decoded.chunk_by_type("ruSt").ok_or("missing chunk")?;
+//! assert_eq!(message.data_as_string()?, "hidden");
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod ancillary;
+pub mod chunk;
+pub mod chunk_type;
+pub mod crypt;
+pub mod encoding;
+pub mod error;
+pub mod ihdr;
+pub mod json;
+pub mod palette;
+pub mod png;
+pub mod raster;
+pub mod regex;
+pub mod selector;
+pub mod sha256;
+pub mod text;
+pub mod trace;
+pub mod zip;
+pub mod zlib;
+
+pub use error::PngError;
+
+pub type Error = PngError;
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,63 @@
+//! Status messages on stderr, controlled by the global `--quiet` and
+//! `--verbose` flags. A command's output on stdout, such as a decoded
+//! message, and errors are printed regardless.
+
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Log {
+    verbosity: Verbosity,
+}
+
+impl Log {
+    pub fn new(quiet: bool, verbose: bool) -> Self {
+        let verbosity = match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, true) => Verbosity::Verbose,
+            (false, false) => Verbosity::Normal,
+        };
+        Self { verbosity }
+    }
+    pub fn is_quiet(&self) -> bool {
+        self.verbosity == Verbosity::Quiet
+    }
+    /// Whether messages meant for `level` are printed
+    pub fn shows(&self, level: Verbosity) -> bool {
+        self.verbosity >= level
+    }
+    /// Status lines and warnings, hidden by `--quiet`
+    pub fn info(&self, message: impl Display) {
+        if self.shows(Verbosity::Normal) {
+            eprintln!("{message}");
+        }
+    }
+    /// What exactly a command changed, only shown with `--verbose`
+    pub fn verbose(&self, message: impl Display) {
+        if self.shows(Verbosity::Verbose) {
+            eprintln!("{message}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        let quiet = Log::new(true, false);
+        assert!(quiet.is_quiet());
+        assert!(!quiet.shows(Verbosity::Normal));
+        let normal = Log::new(false, false);
+        assert!(normal.shows(Verbosity::Normal));
+        assert!(!normal.shows(Verbosity::Verbose));
+        assert!(Log::new(false, true).shows(Verbosity::Verbose));
+    }
+}
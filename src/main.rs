@@ -1,77 +1,1477 @@
 use std::{
     fs::File,
-    io::{Read, Seek, Write},
-    path::Path,
+    io::{Read, Write},
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
 use args::Commands;
 use clap::Parser;
+use pngme::{
+    ancillary, chunk, chunk_type, crypt, encoding, json, palette, png, raster, regex, selector,
+    text, zip, PngError, Result,
+};
 
 mod args;
-mod chunk;
-mod chunk_type;
 mod commands;
-mod png;
+mod config;
+mod log;
+
+/// How many chunks `verify` checks between progress lines
+const VERIFY_PROGRESS_INTERVAL: usize = 1000;
+
+/// How much of each chunk's data `custom` shows
+const CUSTOM_PREVIEW_CHARS: usize = 40;
 
-pub type Error = Box<dyn std::error::Error>;
-pub type Result<T> = std::result::Result<T, Error>;
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {e}");
+        std::process::exit(exit_code(&e));
+    }
+}
 
-fn main() -> Result<()> {
-    let cli = args::Cli::parse();
+/// 1 when a chunk isn't found, 2 when the input isn't a valid PNG and 3 for
+/// any other error. Usage errors exit with 2 from clap.
+fn exit_code(error: &PngError) -> i32 {
+    match error {
+        PngError::ChunkNotFound(_) => 1,
+        PngError::InvalidSignature
+        | PngError::CrcMismatch { .. }
+        | PngError::TooShort { .. }
+        | PngError::Malformed(_) => 2,
+        PngError::Io(_) | PngError::Other(_) => 3,
+    }
+}
+
+fn run() -> Result<()> {
+    let mut cli = args::Cli::parse();
+    config::Config::load()?.apply(&mut cli)?;
+    let log = log::Log::new(cli.quiet, cli.verbose);
+    let dry_run = cli.dry_run;
+    let use_color = commands::use_color(cli.color);
+    pngme::trace::set_enabled(cli.trace);
     match cli.commands {
         Commands::Encode {
             file_path,
             chunk_type,
             message,
             output_file,
+            output_dir,
+            in_place,
+            messages,
+            message_file,
+            data_file,
+            append_from,
+            donor_type,
+            base64,
+            show_changes,
+            itxt,
+            language,
+            translated_keyword,
+            itxt_compressed,
+            text_keyword,
+            with_checksum,
+            random_position,
+            index,
+            seed,
+            compress,
+            passphrase,
+            deterministic,
+            warn_secrets,
+            strict,
+            recompute_crcs,
+            limit_chunk_size,
+            chunk_size,
         } => {
-            let bytes = get_file_bytes(&file_path)?;
-            let mut png = png::Png::try_from(&bytes[..])?;
-            let chunk = chunk::Chunk::new(
-                chunk_type::ChunkType::from_str(&chunk_type)?,
-                message.as_bytes().to_vec(),
-            );
-            png.append_chunk(chunk);
-            let mut output_file = match output_file {
-                Some(output_file_path) => File::create(output_file_path)?,
-                None => File::create(file_path)?,
-            };
-            output_file.write(&png.as_bytes())?;
+            if let (Some(donor), Some(donor_type)) = (append_from, donor_type) {
+                commands::check_chunk_type_arg(&donor_type)?;
+                // Without a chunk type and message, the output file is the
+                // first positional argument after the target
+                let mut rest = [chunk_type, message]
+                    .into_iter()
+                    .flatten()
+                    .map(PathBuf::from);
+                let output_file = rest.next().or(output_file);
+                if rest.next().is_some() {
+                    return Err(
+                        "With --append-from, pass only the target and an output file".into(),
+                    );
+                }
+                check_overwrite(
+                    &file_path,
+                    output_file.as_deref(),
+                    output_dir.is_some(),
+                    in_place,
+                )?;
+                let donor_png = png::Png::try_from(get_readable_bytes(&donor)?)?;
+                let chunks = donor_png
+                    .chunks_by_type(&donor_type)
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if chunks.is_empty() {
+                    return Err(PngError::ChunkNotFound(format!(
+                        "{donor_type} in {}",
+                        donor.display()
+                    )));
+                }
+                rewrite_files(
+                    &file_path,
+                    output_file,
+                    output_dir,
+                    recompute_crcs,
+                    &log,
+                    dry_run,
+                    |png| {
+                        for chunk in chunks.clone() {
+                            png.try_append_chunk(chunk)?;
+                        }
+                        log.info(format_args!(
+                            "Copied {} {donor_type} chunks from {}",
+                            chunks.len(),
+                            donor.display()
+                        ));
+                        Ok(())
+                    },
+                )?;
+                return Ok(());
+            }
+            let chunk_type = chunk_type.ok_or("Pass a chunk type or --append-from")?;
+            commands::check_chunk_type_arg(&chunk_type)?;
+            // With the message given by an option, the positional message is
+            // really the output file
+            let by_option = !messages.is_empty() || message_file.is_some() || data_file.is_some();
+            if by_option && message.is_some() && output_file.is_some() {
+                return Err(
+                    "Pass either a message or --message, --message-file or --data-file, not both"
+                        .into(),
+                );
+            }
+            let (message, output_file) = match by_option {
+                true => (None, message.map(PathBuf::from).or(output_file)),
+                false => (message, output_file),
+            };
+            let payload = match data_file {
+                Some(data_file) => std::fs::read(data_file)?,
+                None => {
+                    let message = match message_file {
+                        Some(message_file) => std::fs::read_to_string(message_file)?,
+                        None if !messages.is_empty() => messages.join("\n"),
+                        None => message.ok_or("Pass a message or --data-file")?,
+                    };
+                    if base64 {
+                        encoding::base64_decode(&message)?
+                    } else {
+                        message.into_bytes()
+                    }
+                }
+            };
+            check_overwrite(
+                &file_path,
+                output_file.as_deref(),
+                output_dir.is_some(),
+                in_place,
+            )?;
+            if warn_secrets {
+                let warnings = commands::secret_warnings(&String::from_utf8_lossy(&payload));
+                for warning in &warnings {
+                    log.info(warning);
+                }
+                if strict && !warnings.is_empty() {
+                    return Err("Not encoding a message that looks like it contains secrets".into());
+                }
+            }
+            let data = match (itxt, text_keyword) {
+                (Some(keyword), _) => {
+                    if chunk_type != "iTXt" {
+                        return Err("--itxt requires the chunk type iTXt".into());
+                    }
+                    text::ITxt {
+                        keyword,
+                        compressed: itxt_compressed,
+                        language,
+                        translated_keyword,
+                        text: String::from_utf8(payload)?,
+                    }
+                    .to_bytes()?
+                }
+                (None, Some(keyword)) => {
+                    if chunk_type != "tEXt" {
+                        return Err("--text-keyword requires the chunk type tEXt".into());
+                    }
+                    text::text_bytes(&keyword, &String::from_utf8(payload)?)?
+                }
+                (None, None) if with_checksum => commands::with_checksum(&payload),
+                (None, None) => payload,
+            };
+            let data = match passphrase {
+                Some(passphrase) => {
+                    let (salt, nonce) = if deterministic {
+                        log.info(
+                            "warning: --deterministic derives the salt from the passphrase and the nonce \
+                             from the message, so anyone can tell when two chunks hold the same message \
+                             under the same passphrase; use it for testing only",
+                        );
+                        crypt::deterministic_salt_and_nonce(
+                            &passphrase,
+                            &data,
+                            crypt::KDF_ITERATIONS,
+                        )
+                    } else {
+                        (commands::random_bytes()?, commands::random_bytes()?)
+                    };
+                    crypt::encrypt(&passphrase, &data, salt, nonce, crypt::KDF_ITERATIONS)
+                }
+                None => data,
+            };
+            let chunk_type = chunk_type::ChunkType::from_str(&chunk_type)?;
+            let warnings = [
+                commands::reserved_bit_warning(&chunk_type),
+                commands::durability_warning(&chunk_type),
+                commands::nul_warning(&chunk_type, &data),
+            ];
+            for warning in warnings.into_iter().flatten() {
+                log.info(warning);
+            }
+            let chunk = if compress {
+                chunk::Chunk::compressed(chunk_type, &data)
+            } else {
+                chunk::Chunk::try_new(chunk_type, data)?
+            };
+            let chunks = match chunk_size {
+                Some(size) => chunk::Chunk::split_data(*chunk.chunk_type(), chunk.data(), size)?,
+                None => vec![chunk],
+            };
+            if let Some(limit) = limit_chunk_size {
+                for chunk in &chunks {
+                    chunk.check_size_limit(limit)?;
+                }
+            }
+            let mut rng = seed.map_or_else(commands::Rng::from_time, commands::Rng::new);
+            let added = |chunk: &chunk::Chunk, index: usize| {
+                let message = format!(
+                    "Added {} chunk ({} bytes) at index {index}",
+                    chunk.chunk_type(),
+                    chunk.length()
+                );
+                match dry_run {
+                    true => log.info(message),
+                    false => log.verbose(message),
+                }
+            };
+            rewrite_files(
+                &file_path,
+                output_file,
+                output_dir,
+                recompute_crcs,
+                &log,
+                dry_run,
+                |png| {
+                    let before = commands::chunk_type_summary(png);
+                    let chunks = chunks.clone();
+                    if let Some(index) = index {
+                        for (offset, chunk) in chunks.into_iter().enumerate() {
+                            added(&chunk, index + offset);
+                            png.try_insert_chunk(index + offset, chunk)?;
+                        }
+                    } else if random_position {
+                        for chunk in chunks {
+                            let positions = png.ancillary_insert_positions();
+                            let index = positions[rng.below(positions.len())];
+                            added(&chunk, index);
+                            png.try_insert_chunk(index, chunk)?;
+                        }
+                    } else {
+                        for chunk in chunks {
+                            added(&chunk, png.append_position());
+                            png.try_append_chunk(chunk)?;
+                        }
+                    }
+                    if show_changes {
+                        eprintln!("before: {before}");
+                        eprintln!("after: {}", commands::chunk_type_summary(png));
+                    }
+                    Ok(())
+                },
+            )?;
         }
         Commands::Decode {
             file_path,
             chunk_type,
+            index,
+            case_insensitive,
+            contains,
+            exit_zero_on_not_found,
+            // Already folded into exit_zero_on_not_found by `Config::apply`
+            no_exit_zero_on_not_found: _,
+            stdin_hex,
+            rename_to_content,
+            lossy,
+            utf16,
+            decompress,
+            passphrase,
+            verify_checksum,
+            printable_only,
+            base64,
+            clipboard,
+            all,
+            join,
         } => {
-            let bytes = get_file_bytes(file_path)?;
-            let png = png::Png::try_from(&bytes[..])?;
-            let chunk = png.chunk_by_type(&chunk_type).ok_or("Chunk not found")?;
-            let data_string = chunk.data_as_string()?;
+            let bytes = get_input_bytes(&file_path, stdin_hex)?;
+            let png = png::Png::try_from(bytes)?;
+            let options = DecodeOptions {
+                decompress,
+                passphrase: passphrase.as_deref(),
+                verify_checksum,
+                lossy,
+                utf16,
+                printable_only,
+                base64,
+            };
+            let (chunk_type, index) = match (chunk_type, index) {
+                (Some(_), Some(_)) | (None, None) => {
+                    return Err("Pass either a chunk type or --index".into())
+                }
+                (chunk_type, index) => (chunk_type.unwrap_or_default(), index),
+            };
+            let contains_text = |chunk: &chunk::Chunk| match &contains {
+                Some(text) => {
+                    std::str::from_utf8(chunk.data()).is_ok_and(|data| data.contains(text))
+                }
+                None => true,
+            };
+            if all {
+                chunk_type::ChunkType::from_str(&chunk_type)?;
+                let mut chunks = png.chunks_matching(&chunk_type, case_insensitive);
+                chunks.retain(|chunk| contains_text(chunk));
+                if chunks.is_empty() && !exit_zero_on_not_found {
+                    return Err(PngError::ChunkNotFound(chunk_type));
+                }
+                if join && !chunks.is_empty() {
+                    let joined = chunk::Chunk::new(
+                        *chunks[0].chunk_type(),
+                        chunk::Chunk::join_data(&chunks)?,
+                    );
+                    println!("{}", decode_message(&joined, &options, &log)?);
+                    return Ok(());
+                }
+                for chunk in chunks {
+                    println!("{}", decode_message(chunk, &options, &log)?);
+                }
+                return Ok(());
+            }
+            let (chunk, wanted) = match (index, &contains) {
+                (Some(index), _) => (
+                    png.chunk_at(index),
+                    format!("index {index}, the file has {} chunks", png.chunks().len()),
+                ),
+                (None, Some(text)) => {
+                    chunk_type::ChunkType::from_str(&chunk_type)?;
+                    let chunk = png.find_chunk(|chunk| {
+                        chunk.has_type(&chunk_type, case_insensitive) && contains_text(chunk)
+                    });
+                    (chunk, format!("{chunk_type} containing {text:?}"))
+                }
+                (None, None) => {
+                    let selector = selector::ChunkSelector::from_str(&chunk_type)?;
+                    let chunk = selector
+                        .resolve_matching(&png, case_insensitive)
+                        .and_then(|index| png.chunk_at(index));
+                    (chunk, chunk_type)
+                }
+            };
+            let chunk = match chunk {
+                Some(chunk) => chunk,
+                None if exit_zero_on_not_found => return Ok(()),
+                None => return Err(PngError::ChunkNotFound(wanted)),
+            };
+            if chunk.chunk_type().to_string() == "iTXt" && !base64 {
+                let itxt = text::ITxt::try_from(chunk.data())?;
+                println!("keyword: {}", itxt.keyword);
+                println!("language: {}", itxt.language);
+                println!("translated keyword: {}", itxt.translated_keyword);
+                println!("compressed: {}", itxt.compressed);
+                println!("{}", itxt.text);
+                return Ok(());
+            }
+            // A tEXt chunk without a NUL has no keyword and is printed as is
+            if chunk.chunk_type().to_string() == "tEXt" && !base64 {
+                if let Ok(entry) = text::TextEntry::try_from(chunk) {
+                    println!("{}: {}", entry.keyword, entry.value);
+                    return Ok(());
+                }
+            }
+            let data_string = decode_message(chunk, &options, &log)?;
             // println!("The chunk's data is:");
-            println!("{data_string}");
+            let copied = clipboard
+                && match commands::copy_to_clipboard(&data_string) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        log.info(format_args!("warning: {e}, printing instead"));
+                        false
+                    }
+                };
+            if !copied {
+                println!("{data_string}");
+            }
+            if let Some(template) = rename_to_content {
+                if file_path == Path::new("-") {
+                    return Err("--rename-to-content needs an input file".into());
+                }
+                let file_name = commands::content_file_name(&template, &data_string)?;
+                let renamed = file_path.with_file_name(file_name);
+                if dry_run {
+                    log.info(format_args!(
+                        "Dry run: would rename {} to {}",
+                        file_path.display(),
+                        renamed.display()
+                    ));
+                } else {
+                    std::fs::rename(&file_path, renamed)?;
+                }
+            }
+        }
+        Commands::Extract {
+            file_path,
+            chunk_type,
+            output_file,
+            all,
+            spool,
+        } => {
+            if spool {
+                return spool_chunk_data(&file_path, &chunk_type, &output_file, &log, dry_run);
+            }
+            let png = png::Png::try_from(get_readable_bytes(&file_path)?)?;
+            if all {
+                chunk_type::ChunkType::from_str(&chunk_type)?;
+                if output_file == Path::new("-") {
+                    return Err(
+                        "--all writes numbered files, pass a file name instead of `-`".into(),
+                    );
+                }
+                let chunks = png.chunks_by_type(&chunk_type);
+                if chunks.is_empty() {
+                    return Err(PngError::ChunkNotFound(chunk_type));
+                }
+                for (n, chunk) in chunks.into_iter().enumerate() {
+                    let path = commands::numbered_path(&output_file, n);
+                    write_file(&path, chunk.data(), &log, dry_run)?;
+                }
+                return Ok(());
+            }
+            let selector = selector::ChunkSelector::from_str(&chunk_type)?;
+            let index = selector
+                .resolve(&png)
+                .ok_or(PngError::ChunkNotFound(chunk_type))?;
+            let data = png.chunks()[index].data();
+            if output_file == Path::new("-") {
+                std::io::stdout().lock().write_all(data)?;
+            } else {
+                write_file(&output_file, data, &log, dry_run)?;
+            }
+        }
+        Commands::Replace {
+            file_path,
+            chunk_type,
+            message,
+            create,
+            output_file,
+            in_place,
+        } => {
+            commands::check_chunk_type_arg(&chunk_type)?;
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            rewrite_files(&file_path, output_file, None, false, &log, dry_run, |png| {
+                let data = message.clone().into_bytes();
+                match png.replace_chunk_data(&chunk_type, data.clone()) {
+                    Err(PngError::ChunkNotFound(_)) if create => {
+                        let chunk_type = chunk_type::ChunkType::from_str(&chunk_type)?;
+                        png.try_append_chunk(chunk::Chunk::new(chunk_type, data))?;
+                        log.info(format_args!("Added a new {chunk_type} chunk"));
+                    }
+                    result => {
+                        result?;
+                        log.info(format_args!(
+                            "Replaced the data of the first {chunk_type} chunk"
+                        ));
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Commands::Stash {
+            file_path,
+            stashed_file,
+            chunk_size,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            let data = std::fs::read(&stashed_file)?;
+            let name = stashed_file
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or("The file to stash needs a UTF-8 file name")?;
+            rewrite_files(&file_path, output_file, None, false, &log, dry_run, |png| {
+                let added = png.stash(name, &data, chunk_size)?;
+                log.info(format_args!(
+                    "Stashed {name} ({} bytes) in {added} {} chunks",
+                    data.len(),
+                    png::Png::STASH_CHUNK_TYPE
+                ));
+                Ok(())
+            })?;
+        }
+        Commands::Unstash {
+            file_path,
+            output_dir,
+        } => {
+            let png = png::Png::try_from(get_readable_bytes(&file_path)?)?;
+            let (name, data) = png.unstash()?;
+            // Only a bare name, so a crafted file can't write outside the directory
+            if Path::new(&name).file_name() != Some(name.as_ref()) {
+                return Err(format!("Stashed file name {name:?} is not a plain file name").into());
+            }
+            let path = output_dir.join(&name);
+            if path.exists() {
+                return Err(format!("{} already exists", path.display()).into());
+            }
+            if !dry_run {
+                std::fs::create_dir_all(&output_dir)?;
+            }
+            write_file(&path, &data, &log, dry_run)?;
         }
         Commands::Remove {
             file_path,
             chunk_type,
+            first,
+            case_insensitive,
+            type_regex,
+            allow_critical,
+            yes,
+            show_changes,
+            recompute_crcs,
+            output_file,
+            output_dir,
+            in_place,
+        } => {
+            check_overwrite(
+                &file_path,
+                output_file.as_deref(),
+                output_dir.is_some(),
+                in_place,
+            )?;
+            let regex = type_regex
+                .as_deref()
+                .map(regex::Regex::from_str)
+                .transpose()?;
+            // Only the standard critical chunks, since dropping an unknown
+            // critical chunk such as RuSt leaves a PNG decoders accept again
+            let confirm_critical = |chunk: &chunk::Chunk| -> Result<()> {
+                let chunk_type = chunk.chunk_type();
+                if chunk_type.is_critical() && chunk_type.is_standard() && !yes {
+                    return Err(format!(
+                        "Removing critical chunk {} breaks the PNG, pass --yes to remove it anyway",
+                        chunk.chunk_type()
+                    )
+                    .into());
+                }
+                Ok(())
+            };
+            let removed_all = |removed: &[chunk::Chunk]| {
+                for chunk in removed {
+                    log.verbose(format_args!(
+                        "Removed {} chunk ({} bytes)",
+                        chunk.chunk_type(),
+                        chunk.length()
+                    ));
+                }
+            };
+            rewrite_files(
+                &file_path,
+                output_file,
+                output_dir,
+                recompute_crcs,
+                &log,
+                dry_run,
+                |png| {
+                    let before = commands::chunk_type_summary(png);
+                    match (&chunk_type, &regex) {
+                        (_, Some(regex)) => {
+                            let matches = |chunk: &chunk::Chunk| {
+                                regex.is_match(&chunk.chunk_type().to_string())
+                            };
+                            if !allow_critical {
+                                if let Some(chunk) = png.chunks().iter().find(|chunk| {
+                                    matches(chunk) && chunk.chunk_type().is_critical()
+                                }) {
+                                    return Err(format!(
+                                    "Pattern matches critical chunk {}, pass --allow-critical to remove it",
+                                    chunk.chunk_type()
+                                )
+                                    .into());
+                                }
+                            }
+                            let removed = png.remove_chunks_where(matches);
+                            log.info(format_args!("Removed {} chunks", removed.len()));
+                            removed_all(&removed);
+                        }
+                        (Some(chunk_type), None) => {
+                            let selector = selector::ChunkSelector::from_str(chunk_type)?;
+                            if !first && !chunk_type.contains([':', '@']) {
+                                let matches = |chunk: &chunk::Chunk| {
+                                    chunk.has_type(chunk_type, case_insensitive)
+                                };
+                                for chunk in png.chunks().iter().filter(|chunk| matches(chunk)) {
+                                    confirm_critical(chunk)?;
+                                }
+                                let removed = png.remove_chunks_where(matches);
+                                if removed.is_empty() {
+                                    return Err(PngError::ChunkNotFound(chunk_type.clone()));
+                                }
+                                log.info(format_args!(
+                                    "Removed {} {chunk_type} chunks",
+                                    removed.len()
+                                ));
+                                removed_all(&removed);
+                            } else {
+                                let index = selector
+                                    .resolve_matching(png, case_insensitive)
+                                    .ok_or_else(|| PngError::ChunkNotFound(chunk_type.clone()))?;
+                                if let Some(chunk) = png.chunk_at(index) {
+                                    confirm_critical(chunk)?;
+                                }
+                                let removed = png.remove_chunk_at(index)?;
+                                log.info(format_args!(
+                                    "Removed {} ({} bytes)",
+                                    removed.chunk_type(),
+                                    removed.length()
+                                ));
+                                log.verbose(format_args!("Removed chunk was at index {index}"));
+                            }
+                        }
+                        (None, None) => unreachable!("clap requires a chunk type or --type-regex"),
+                    }
+                    if show_changes {
+                        eprintln!("before: {before}");
+                        eprintln!("after: {}", commands::chunk_type_summary(png));
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+        Commands::Rename {
+            file_path,
+            chunk_type_file,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            let renames = commands::parse_type_mapping(&std::fs::read_to_string(chunk_type_file)?)?;
+            let bytes = get_file_bytes(&file_path)?;
+            let mut png = png::Png::try_from(bytes)?;
+            let counts = png.rename_chunks(&renames);
+            for ((from, to), count) in renames.iter().zip(counts) {
+                log.info(format_args!("Renamed {count} {from} chunks to {to}"));
+            }
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
+        }
+        Commands::Print {
+            file_path,
+            lenient,
+            // Already folded into lenient by `Config::apply`
+            no_lenient: _,
+            stdin_hex,
+            skip_idat,
+            data_uri,
+            width,
+            adler,
+            diagnose,
+            explain,
+            tail,
+            assume_png,
+            json,
+            decimal_colors,
+            full,
+            type_filter,
+        } => {
+            let width = width.or_else(commands::terminal_width);
+            let wanted = commands::type_filter(&type_filter)?;
+            let shown = |png: &png::Png| {
+                let mut png = png.tail(tail.unwrap_or(usize::MAX));
+                png.remove_chunks_where(|chunk| !wanted(chunk));
+                png
+            };
+            if skip_idat {
+                let mut reader = std::io::BufReader::new(File::open(&file_path)?);
+                let png = png::Png::metadata_from_seekable(&mut reader)?;
+                let interpreters = color_interpreters(&png, decimal_colors);
+                let size = png.size_summary();
+                let png = shown(&png);
+                print_png(&png, size, &interpreters, width, explain, adler, full);
+                return Ok(());
+            }
+            let bytes = get_input_bytes(&file_path, stdin_hex)?;
+            if diagnose {
+                if let Some(diagnostic) = png::Png::diagnose(&bytes) {
+                    eprintln!("{diagnostic}");
+                    // The parser's own error, so the exit code says why
+                    png::Png::try_from(&bytes[..])?;
+                    return Err(PngError::Malformed("Failed to parse PNG".into()));
+                }
+            }
+            let png = if lenient {
+                let (png, resyncs) = png::Png::try_from_lenient(&bytes[..])?;
+                for resync in resyncs {
+                    log.info(format_args!(
+                        "Skipped {} bytes at offset {}, resynced at offset {}",
+                        resync.to - resync.from,
+                        resync.from,
+                        resync.to
+                    ));
+                }
+                png
+            } else if assume_png {
+                png::Png::from_chunk_stream(&bytes)?
+            } else {
+                png::Png::try_from(bytes)?
+            };
+            for check in [png.validate_ihdr_first(), png.validate_ihdr()] {
+                if let Err(e) = check {
+                    log.info(format_args!("warning: {e}"));
+                }
+            }
+            if data_uri {
+                println!("{}", png.data_uri());
+                return Ok(());
+            }
+            if json {
+                println!("{}", shown(&png).to_json());
+                return Ok(());
+            }
+            let interpreters = color_interpreters(&png, decimal_colors);
+            let size = png.size_summary();
+            let png = shown(&png);
+            print_png(&png, size, &interpreters, width, explain, adler, full);
+        }
+        Commands::List {
+            file_path,
+            reverse,
+            limit,
+            sparkline,
+            assume_png,
+            json,
+            type_filter,
+        } => {
+            let wanted = commands::type_filter(&type_filter)?;
+            if json {
+                let bytes = get_readable_bytes(file_path)?;
+                let png = if assume_png {
+                    png::Png::from_chunk_stream(&bytes)?
+                } else {
+                    png::Png::try_from(bytes)?
+                };
+                let mut chunks = png
+                    .chunks()
+                    .iter()
+                    .filter(|chunk| wanted(chunk))
+                    .collect::<Vec<_>>();
+                if reverse {
+                    chunks.reverse();
+                }
+                let chunks = chunks
+                    .into_iter()
+                    .take(limit.unwrap_or(usize::MAX))
+                    .map(chunk::Chunk::to_json)
+                    .collect();
+                println!("{}", json::Json::Array(chunks));
+                return Ok(());
+            }
+            let reader = open_readable(&file_path)?;
+            let reader = if assume_png {
+                chunk::ChunkReader::new(reader)
+            } else {
+                png::Png::read_chunks(reader)?
+            };
+            let mut chunks = Vec::new();
+            let mut offset = png::Png::STANDARD_HEADER.len();
+            for (index, chunk) in reader.enumerate() {
+                let chunk = chunk?;
+                if wanted(&chunk) {
+                    chunks.push(png::ChunkMeta::new(index, offset, &chunk));
+                }
+                offset += 12 + chunk.data().len();
+            }
+            if sparkline {
+                let lengths = chunks.iter().map(|meta| meta.length).collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    commands::sparkline(&lengths, commands::locale_is_utf8())
+                );
+                println!("{} chunks", chunks.len());
+                return Ok(());
+            }
+            if reverse {
+                chunks.reverse();
+            }
+            for meta in chunks.iter().take(limit.unwrap_or(usize::MAX)) {
+                println!("{meta}");
+            }
+            println!("{} chunks", chunks.len());
+        }
+        Commands::Count {
+            file_path,
+            chunk_type,
+            case_insensitive,
+        } => {
+            commands::check_chunk_type_arg(&chunk_type)?;
+            let mut count = 0;
+            for chunk in png::Png::read_chunks(open_readable(&file_path)?)? {
+                if chunk?.has_type(&chunk_type, case_insensitive) {
+                    count += 1;
+                }
+            }
+            println!("{count}");
+        }
+        Commands::Custom { file_path } => {
+            let png = png::Png::try_from(get_readable_bytes(file_path)?)?;
+            for (index, chunk) in png.private_chunks() {
+                let preview = chunk
+                    .data_as_string_lossy()
+                    .chars()
+                    .take(CUSTOM_PREVIEW_CHARS)
+                    .collect::<String>();
+                println!("{index:>4}  {}  {preview:?}", chunk.chunk_type());
+            }
+        }
+        Commands::SetGamma {
+            file_path,
+            gamma,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            let bytes = get_file_bytes(&file_path)?;
+            let mut png = png::Png::try_from(bytes)?;
+            png.set_gamma(ancillary::Gamma::from_value(gamma)?)?;
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
+        }
+        Commands::MergeIdat {
+            file_path,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            let bytes = get_file_bytes(&file_path)?;
+            let mut png = png::Png::try_from(bytes)?;
+            let count = png.merge_idat();
+            log.info(format_args!("Merged {count} IDAT chunks"));
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
+        }
+        Commands::Clean {
+            file_path,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            let mut png = png::Png::try_from(get_file_bytes(&file_path)?)?;
+            let removed = png.retain_critical();
+            log.info(format_args!("Stripped {removed} ancillary chunks"));
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
+        }
+        Commands::Optimize {
+            file_path,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            let bytes = get_file_bytes(&file_path)?;
+            let mut png = png::Png::try_from_bytes(&bytes, false)?;
+            let idat = png.merge_idat();
+            let duplicates = png.remove_duplicate_ancillary();
+            let empty = png.remove_empty_chunks();
+            let crcs = png.recompute_crcs();
+            log.info(format_args!(
+                "Merged {idat} IDAT chunks, removed {duplicates} duplicate and {empty} empty chunks, fixed {crcs} CRCs"
+            ));
+            let size = png.size_summary().total;
+            log.info(format_args!(
+                "Saved {} bytes ({} -> {size})",
+                bytes.len() as i64 - size as i64,
+                bytes.len()
+            ));
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
+        }
+        Commands::SplitIdat {
+            file_path,
+            size,
+            limit_chunk_size,
+            output_file,
+            in_place,
         } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
             let bytes = get_file_bytes(&file_path)?;
-            let mut png = png::Png::try_from(&bytes[..])?;
-            png.remove_chunk(&chunk_type)?;
-            let mut file = File::create(file_path)?;
-            file.write(&png.as_bytes())?;
+            let mut png = png::Png::try_from(bytes)?;
+            let size = size
+                .into_iter()
+                .chain(limit_chunk_size)
+                .min()
+                .ok_or("Pass --size or --limit-chunk-size")?;
+            let count = png.split_idat(size)?;
+            if let Some(limit) = limit_chunk_size {
+                for chunk in png.chunks() {
+                    chunk.check_size_limit(limit)?;
+                }
+            }
+            log.info(format_args!("Split IDAT data into {count} chunks"));
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
         }
-        Commands::Print { file_path } => {
+        Commands::FixCrc {
+            file_path,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
+            let mut png = read_png_for_rewrite(&file_path, true)?;
+            log.info(format_args!("Fixed {} chunk CRCs", png.recompute_crcs()));
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
+        }
+        Commands::Seal {
+            file_path,
+            output_file,
+            in_place,
+        } => {
+            check_overwrite(&file_path, output_file.as_deref(), false, in_place)?;
             let bytes = get_file_bytes(&file_path)?;
-            let png = png::Png::try_from(&bytes[..])?;
-            println!("{png}");
+            let mut png = png::Png::try_from(bytes)?;
+            png.seal();
+            write_png(&png, &file_path, output_file, &log, dry_run)?;
+        }
+        Commands::VerifySeal { file_path } => {
+            let bytes = get_readable_bytes(&file_path)?;
+            let png = png::Png::try_from(bytes)?;
+            if !png.verify_seal()? {
+                return Err("Seal does not match file contents".into());
+            }
+            println!("Seal OK");
+        }
+        Commands::Verify { file_path, strict } => {
+            let quiet = log.is_quiet();
+            let bytes = get_readable_bytes(&file_path)?;
+            let png = png::Png::try_from_keeping_bad_crcs(&bytes)?;
+            // The validator also checks chunks after IEND, so it says how many
+            let mut total = png.chunks().len();
+            let findings = png.validate_with_progress(|checked, of| {
+                total = of;
+                if !quiet && checked.is_multiple_of(VERIFY_PROGRESS_INTERVAL) {
+                    eprintln!("Checked {checked}/{total} chunks");
+                }
+            });
+            let is_failure = |finding: &png::ValidationError| strict || !finding.is_warning();
+            for finding in &findings {
+                match is_failure(finding) {
+                    true => println!("{finding}"),
+                    false => log.info(format_args!("warning: {finding}")),
+                }
+            }
+            let crc_errors = findings
+                .iter()
+                .filter(|finding| matches!(finding, png::ValidationError::BadCrc { .. }))
+                .count();
+            if !quiet {
+                println!(
+                    "{total}/{total} chunks, {crc_errors} CRC errors, {} structural warnings",
+                    findings.len() - crc_errors
+                );
+            }
+            if findings.iter().any(is_failure) {
+                std::process::exit(1);
+            }
+            println!("OK");
+        }
+        Commands::Text { file_path, format } => {
+            let png = png::Png::try_from(get_readable_bytes(file_path)?)?;
+            let entries = png.all_text()?;
+            match format {
+                args::TextFormat::Plain => {
+                    for entry in entries {
+                        println!("{}: {}", entry.keyword, entry.value);
+                    }
+                }
+                args::TextFormat::Json => {
+                    let entries = entries.iter().map(text::TextEntry::to_json).collect();
+                    println!("{}", json::Json::Array(entries));
+                }
+            }
+        }
+        Commands::CompareImage { a, b } => {
+            let a = png::Png::try_from(get_readable_bytes(a)?)?;
+            let b = png::Png::try_from(get_readable_bytes(b)?)?;
+            let a = raster::Raster::try_from(&a)?;
+            let b = raster::Raster::try_from(&b)?;
+            match a.first_difference(&b)? {
+                None => println!("Pixels identical"),
+                Some((x, y)) => {
+                    println!("First differing pixel at ({x}, {y})");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::CompareMeta { a, b } => {
+            let a = png::Png::try_from(get_readable_bytes(a)?)?;
+            let b = png::Png::try_from(get_readable_bytes(b)?)?;
+            let changes = commands::metadata_changes(&a, &b);
+            if changes.is_empty() {
+                println!("Metadata identical");
+            } else {
+                for change in changes {
+                    println!("{change}");
+                }
+                std::process::exit(1);
+            }
+        }
+        Commands::Diff { a, b } => {
+            let a = png::Png::try_from(get_readable_bytes(a)?)?;
+            let b = png::Png::try_from(get_readable_bytes(b)?)?;
+            let differences = commands::chunk_differences(&a, &b);
+            if differences.is_empty() {
+                println!("Chunks identical");
+            } else {
+                for difference in differences {
+                    println!("{difference}");
+                }
+                std::process::exit(1);
+            }
+        }
+        Commands::SelfTest => match commands::self_test() {
+            Ok(()) => println!("PASS"),
+            Err(e) => {
+                println!("FAIL: {e}");
+                std::process::exit(1);
+            }
+        },
+        Commands::Palette {
+            file_path,
+            swatch,
+            decimal_colors,
+        } => {
+            let png = png::Png::try_from(get_readable_bytes(file_path)?)?;
+            let plte = png
+                .chunk_by_type("PLTE")
+                .ok_or_else(|| PngError::ChunkNotFound("PLTE".to_string()))?;
+            let palette = palette::Palette::try_from(plte.data())?;
+            if swatch {
+                for (index, [r, g, b]) in palette.0.iter().enumerate() {
+                    let swatch = match use_color {
+                        true => format!("  \x1b[48;2;{r};{g};{b}m    \x1b[0m"),
+                        false => String::new(),
+                    };
+                    let color = match decimal_colors {
+                        true => format!("rgb({r}, {g}, {b})"),
+                        false => palette.hex(index).unwrap(),
+                    };
+                    println!("{index:>3}  {color}{swatch}");
+                }
+            } else if decimal_colors {
+                print!("{palette:#}");
+            } else {
+                print!("{palette}");
+            }
+        }
+        Commands::Detect { file_path } => {
+            let png = png::Png::try_from(get_readable_bytes(file_path)?)?;
+            let findings = commands::detect_embedded(&png);
+            for (index, confidence, reason) in &findings {
+                let chunk_type = png.chunks()[*index].chunk_type();
+                println!("@{index} {chunk_type}: {reason} ({confidence} confidence)");
+            }
+            match findings.iter().map(|(_, confidence, _)| confidence).max() {
+                Some(confidence) => println!(
+                    "this file likely contains an embedded message ({confidence} confidence)"
+                ),
+                None => println!("no signs of an embedded message"),
+            }
+        }
+        Commands::Scan { file_path } => {
+            let png = png::Png::try_from(get_readable_bytes(file_path)?)?;
+            // Valid UTF-8 alone would let through e.g. sRGB's single 0 byte
+            let readable = |text: &str| {
+                !text.is_empty()
+                    && !text
+                        .chars()
+                        .any(|c| c.is_control() && !c.is_ascii_whitespace())
+            };
+            for chunk in png.chunks() {
+                if chunk.chunk_type().is_critical() {
+                    continue;
+                }
+                match chunk.data_as_string() {
+                    Ok(text) if readable(&text) => println!("{}: {text}", chunk.chunk_type()),
+                    _ => {}
+                }
+            }
         }
     }
     Ok(())
 }
 
+fn print_png(
+    png: &png::Png,
+    size: png::SizeSummary,
+    interpreters: &ancillary::Interpreters,
+    width: Option<usize>,
+    explain: bool,
+    adler: bool,
+    full: bool,
+) {
+    let mut display = png.display_with(interpreters).sized(size);
+    if full {
+        display = display.showing(usize::MAX);
+    }
+    match (width, explain) {
+        (Some(width), true) => println!("{display:#width$}"),
+        (Some(width), false) => println!("{display:width$}"),
+        (None, true) => println!("{display:#}"),
+        (None, false) => println!("{display}"),
+    }
+    if adler {
+        for chunk in png.into_iter().filter(|chunk| !chunk.is_data_skipped()) {
+            println!("{} adler32: {:08x}", chunk.chunk_type(), chunk.adler32());
+        }
+    }
+}
+
+/// Interpreters that show bKGD as a hex color for the image's bit depth,
+/// unless `decimal` keeps the built-in `rgb(r, g, b)` form
+fn color_interpreters(png: &png::Png, decimal: bool) -> ancillary::Interpreters {
+    let interpreters = ancillary::Interpreters::new();
+    let Some(ihdr) = png.ihdr().filter(|_| !decimal) else {
+        return interpreters;
+    };
+    interpreters.register(*b"bKGD", move |data| {
+        match ancillary::Background::parse(data, ihdr.color_type) {
+            Ok(background) => match background.hex(ihdr.bit_depth) {
+                Some(hex) => format!("background {hex}"),
+                None => background.to_string(),
+            },
+            Err(e) => format!("invalid ({e})"),
+        }
+    })
+}
+
+/// The `decode` flags that control how chunk data becomes text
+struct DecodeOptions<'a> {
+    decompress: bool,
+    passphrase: Option<&'a str>,
+    verify_checksum: bool,
+    lossy: bool,
+    utf16: Option<args::ByteOrder>,
+    printable_only: bool,
+    base64: bool,
+}
+
+/// The chunk's data as text (UTF-8 unless `utf16` or `printable_only` is
+/// given), first inflated with `decompress` or decrypted with `passphrase`,
+/// then checking and stripping its `--with-checksum` trailer with
+/// `verify_checksum`
+fn decode_message(chunk: &chunk::Chunk, options: &DecodeOptions, log: &log::Log) -> Result<String> {
+    let DecodeOptions {
+        decompress,
+        passphrase,
+        verify_checksum,
+        lossy,
+        utf16,
+        printable_only,
+        base64,
+    } = *options;
+    let unwrapped;
+    let chunk = if decompress || passphrase.is_some() {
+        let data = match passphrase {
+            Some(passphrase) => crypt::decrypt(passphrase, chunk.data())?,
+            None => chunk.decompressed_data()?,
+        };
+        unwrapped = chunk::Chunk::new(*chunk.chunk_type(), data);
+        &unwrapped
+    } else {
+        chunk
+    };
+    let checked;
+    let chunk = if verify_checksum {
+        match commands::split_checksum(chunk.data()) {
+            Some((_, false)) => return Err("message integrity FAILED".into()),
+            Some((message, true)) => {
+                log.info("message integrity OK");
+                checked = chunk::Chunk::new(*chunk.chunk_type(), message.to_vec());
+                &checked
+            }
+            None => return Err("The message has no checksum from --with-checksum".into()),
+        }
+    } else {
+        chunk
+    };
+    if base64 {
+        Ok(encoding::base64_encode(chunk.data()))
+    } else if let Some(order) = utf16 {
+        encoding::utf16_decode(chunk.data(), order == args::ByteOrder::Be)
+    } else if printable_only {
+        Ok(commands::escape_unprintable(chunk.data()))
+    } else if lossy {
+        Ok(chunk.data_as_string_lossy())
+    } else {
+        chunk.data_as_string()
+    }
+}
+
+/// Reads a file that will be written back, keeping chunks with a bad CRC
+/// only when they're about to be fixed with `--recompute-crcs`
+fn read_png_for_rewrite(file_path: &Path, recompute_crcs: bool) -> Result<png::Png> {
+    png::Png::try_from_bytes(&get_file_bytes(file_path)?, !recompute_crcs)
+}
+
+/// Reads the file, applies `edit`, fixes CRCs with `recompute_crcs` and writes
+/// it to `output_file`, into `output_dir` or back over the input. A glob
+/// pattern is processed one matching file at a time, going on past failures,
+/// with a summary printed at the end.
+fn rewrite_files(
+    file_path: &Path,
+    output_file: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    recompute_crcs: bool,
+    log: &log::Log,
+    dry_run: bool,
+    mut edit: impl FnMut(&mut png::Png) -> Result<()>,
+) -> Result<()> {
+    if output_file.is_some() && output_dir.is_some() {
+        return Err("Pass either an output file or --output-dir, not both".into());
+    }
+    if let (Some(output_dir), false) = (&output_dir, dry_run) {
+        std::fs::create_dir_all(output_dir)?;
+    }
+    let mut rewrite = |path: &Path, output_file: Option<PathBuf>| -> Result<()> {
+        let mut png = read_png_for_rewrite(path, recompute_crcs)?;
+        edit(&mut png)?;
+        if recompute_crcs {
+            log.info(format_args!("Fixed {} chunk CRCs", png.recompute_crcs()));
+        }
+        let output_file = match (&output_dir, path.file_name()) {
+            (Some(output_dir), Some(name)) if path != Path::new("-") => Some(output_dir.join(name)),
+            _ => output_file,
+        };
+        write_png(&png, path, output_file, log, dry_run)
+    };
+    let Some(files) = commands::expand_glob(file_path)? else {
+        return rewrite(file_path, output_file);
+    };
+    if output_file.is_some() {
+        return Err("An output file can't hold every match of a glob, pass --output-dir".into());
+    }
+    let mut failed = 0;
+    for path in &files {
+        if let Err(e) = rewrite(path, None) {
+            eprintln!("error: {}: {e}", path.display());
+            failed += 1;
+        }
+    }
+    log.info(format_args!(
+        "{} succeeded, {failed} failed",
+        files.len() - failed
+    ));
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{failed} of {} files failed", files.len()).into()),
+    }
+}
+
+/// Refuses to overwrite `file_path` unless `--in-place` was passed, whether
+/// no output was given or the output file is the input under another name.
+/// Stdin has no file to overwrite and is left to `write_png` to report.
+fn check_overwrite(
+    file_path: &Path,
+    output_file: Option<&Path>,
+    output_dir: bool,
+    in_place: bool,
+) -> Result<()> {
+    if in_place || file_path == Path::new("-") {
+        return Ok(());
+    }
+    match output_file {
+        Some(output_file) if commands::same_file(file_path, output_file) => Err(format!(
+            "The output file {} is the input file, pass --in-place to overwrite it",
+            output_file.display()
+        )
+        .into()),
+        Some(_) => Ok(()),
+        None if output_dir => Ok(()),
+        None => Err(format!(
+            "Refusing to overwrite {} in place, pass an output file or --in-place",
+            file_path.display()
+        )
+        .into()),
+    }
+}
+
+/// Writes to `output_file` (stdout for `-`), or back over `file_path` when
+/// none was given. With `dry_run` only the size and destination are reported.
+fn write_png(
+    png: &png::Png,
+    file_path: &Path,
+    output_file: Option<PathBuf>,
+    log: &log::Log,
+    dry_run: bool,
+) -> Result<()> {
+    let output_path = match output_file {
+        Some(output_file) => output_file,
+        None if file_path == Path::new("-") => {
+            return Err("Input was read from stdin, pass an output file to write to".into())
+        }
+        None => file_path.to_path_buf(),
+    };
+    if dry_run {
+        let size = png.size_summary().total;
+        let destination = match output_path == Path::new("-") {
+            true => "stdout".to_string(),
+            false => output_path.display().to_string(),
+        };
+        let current = match std::fs::metadata(&output_path) {
+            Ok(metadata) if output_path != Path::new("-") => {
+                format!(", replacing {} bytes", metadata.len())
+            }
+            _ => String::new(),
+        };
+        log.info(format_args!(
+            "Dry run: would write {size} bytes to {destination}{current}"
+        ));
+        return Ok(());
+    }
+    if output_path == Path::new("-") {
+        let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+        png.write_to(&mut stdout)?;
+        stdout.flush()?;
+        log.verbose("Wrote the PNG to stdout");
+    } else {
+        let mut file = std::io::BufWriter::new(File::create(&output_path)?);
+        png.write_to(&mut file)?;
+        file.flush()?;
+        log.verbose(format_args!("Wrote the PNG to {}", output_path.display()));
+    }
+    Ok(())
+}
+
+/// Writes `data` to `path` and reports it, or with `dry_run` only reports
+/// what would be written
+fn write_file(path: &Path, data: &[u8], log: &log::Log, dry_run: bool) -> Result<()> {
+    if dry_run {
+        log.info(format_args!(
+            "Dry run: would write {} bytes to {}",
+            data.len(),
+            path.display()
+        ));
+        return Ok(());
+    }
+    std::fs::write(path, data)?;
+    log.info(format_args!(
+        "Wrote {} bytes to {}",
+        data.len(),
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Streams the data of the chunk `chunk` selects from `file_path` to `output_path` (stdout
+/// for `-`) without loading either whole. The data is written before its CRC
+/// is checked, so a file left by a failed copy is removed.
+fn spool_chunk_data(
+    file_path: &Path,
+    chunk: &str,
+    output_path: &Path,
+    log: &log::Log,
+    dry_run: bool,
+) -> Result<()> {
+    let selector = selector::ChunkSelector::from_str(chunk)?;
+    let mut reader = open_readable(file_path)?;
+    let not_found = || PngError::ChunkNotFound(chunk.to_string());
+    if output_path == Path::new("-") {
+        let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+        png::Png::stream_chunk_data_where(&mut reader, selector.matcher(), &mut stdout)?
+            .ok_or_else(not_found)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+    if dry_run {
+        let mut sink = std::io::sink();
+        let written =
+            png::Png::stream_chunk_data_where(&mut reader, selector.matcher(), &mut sink)?
+                .ok_or_else(not_found)?;
+        log.info(format_args!(
+            "Dry run: would write {written} bytes to {}",
+            output_path.display()
+        ));
+        return Ok(());
+    }
+    let mut file = std::io::BufWriter::new(File::create(output_path)?);
+    let result = png::Png::stream_chunk_data_where(&mut reader, selector.matcher(), &mut file)
+        .and_then(|written| written.ok_or_else(not_found))
+        .and_then(|written| {
+            file.flush()?;
+            Ok(written)
+        });
+    match result {
+        Ok(written) => {
+            log.info(format_args!(
+                "Wrote {written} bytes to {}",
+                output_path.display()
+            ));
+            Ok(())
+        }
+        Err(e) => {
+            drop(file);
+            std::fs::remove_file(output_path)?;
+            Err(e)
+        }
+    }
+}
+
+fn get_input_bytes(file_path: &Path, stdin_hex: bool) -> Result<Vec<u8>> {
+    if !stdin_hex {
+        return get_readable_bytes(file_path);
+    }
+    if file_path != Path::new("-") {
+        return Err("--stdin-hex reads from stdin, pass `-` as the file path".into());
+    }
+    let mut hex = String::new();
+    std::io::stdin().read_to_string(&mut hex)?;
+    encoding::hex_decode(&hex)
+}
+
+/// Opens the file (or stdin for `-`) to be read chunk by chunk instead of
+/// loaded whole. Accepts zip entries like `get_readable_bytes`, though those
+/// are inflated into memory.
+fn open_readable(file_path: &Path) -> Result<Box<dyn Read>> {
+    if let Some((archive, entry)) = zip::split_entry_path(file_path) {
+        let data = zip::read_entry(&get_file_bytes(archive)?, &entry)?;
+        return Ok(Box::new(std::io::Cursor::new(data)));
+    }
+    if file_path == Path::new("-") {
+        return Ok(Box::new(std::io::stdin().lock()));
+    }
+    Ok(Box::new(std::io::BufReader::new(File::open(file_path)?)))
+}
+
+/// Like `get_file_bytes`, but also accepts `archive.zip!inner/image.png` to
+/// read a zip entry; only for commands that don't write back to the path
+fn get_readable_bytes(file_path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    match zip::split_entry_path(file_path.as_ref()) {
+        Some((archive, entry)) => zip::read_entry(&get_file_bytes(archive)?, &entry),
+        None => get_file_bytes(file_path),
+    }
+}
+
+/// Reads the whole file, or all of stdin when the path is `-`
 fn get_file_bytes(file_path: impl AsRef<Path>) -> Result<Vec<u8>> {
-    let mut file = File::open(file_path)?;
     let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
+    if file_path.as_ref() == Path::new("-") {
+        std::io::stdin().read_to_end(&mut buf)?;
+    } else {
+        File::open(file_path)?.read_to_end(&mut buf)?;
+    }
     Ok(buf)
 }
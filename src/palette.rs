@@ -0,0 +1,76 @@
+use std::fmt::Display;
+
+/// The colors of an indexed image, from its `PLTE` chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette(pub Vec<[u8; 3]>);
+
+impl TryFrom<&[u8]> for Palette {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.is_empty() || !value.len().is_multiple_of(3) || value.len() > 256 * 3 {
+            return Err(format!(
+                "PLTE must hold 1 to 256 RGB entries of 3 bytes, found {} bytes",
+                value.len()
+            )
+            .into());
+        }
+        Ok(Self(
+            value
+                .chunks(3)
+                .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                .collect(),
+        ))
+    }
+}
+
+impl Palette {
+    /// The entry as a `#rrggbb` color
+    pub fn hex(&self, index: usize) -> Option<String> {
+        let [r, g, b] = self.0.get(index)?;
+        Some(format!("#{r:02x}{g:02x}{b:02x}"))
+    }
+}
+
+/// One `#rrggbb` entry per line, or `rgb(r, g, b)` with `{:#}`
+impl Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, [r, g, b]) in self.0.iter().enumerate() {
+            if f.alternate() {
+                writeln!(f, "{index:>3}  rgb({r}, {g}, {b})")?;
+            } else {
+                writeln!(f, "{index:>3}  {}", self.hex(index).unwrap())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::try_from(&[255, 0, 0, 0, 128, 255, 16, 16, 16][..]).unwrap();
+        assert_eq!(palette.0, [[255, 0, 0], [0, 128, 255], [16, 16, 16]]);
+        assert_eq!(palette.hex(1).unwrap(), "#0080ff");
+        assert_eq!(palette.hex(3), None);
+        assert_eq!(
+            palette.to_string(),
+            "  0  #ff0000\n  1  #0080ff\n  2  #101010\n"
+        );
+        assert_eq!(
+            format!("{palette:#}"),
+            "  0  rgb(255, 0, 0)\n  1  rgb(0, 128, 255)\n  2  rgb(16, 16, 16)\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_palette() {
+        assert!(Palette::try_from(&[][..]).is_err());
+        assert!(Palette::try_from(&[1, 2, 3, 4][..]).is_err());
+        assert!(Palette::try_from(&[0; 257 * 3][..]).is_err());
+        assert!(Palette::try_from(&[0; 256 * 3][..]).is_ok());
+    }
+}
@@ -1,209 +1,2732 @@
-use std::fmt::Display;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    str::FromStr,
+};
 
-use crate::chunk::Chunk;
+use crate::{
+    ancillary::{Gamma, Interpreters},
+    chunk::{Chunk, ChunkReader, CRC},
+    chunk_type::ChunkType,
+    encoding::base64_encode,
+    ihdr::IhdrInfo,
+    json::Json,
+    sha256::sha256,
+    text::TextEntry,
+    trace, PngError,
+};
 
+/// `Png::default()` is the PNG signature with no chunks at all, so the first
+/// chunk appended (including via `append_chunk_checked`) can be IHDR
+#[derive(Default, PartialEq, Eq, Hash)]
 pub struct Png {
     chunks: Vec<Chunk>,
+    /// Whatever followed IEND in the parsed file, written back out after it
+    trailing: Vec<u8>,
+}
+
+/// Summary of a single chunk and where it sits in the serialized file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkMeta {
+    pub index: usize,
+    pub chunk_type: String,
+    pub offset: usize,
+    pub length: u32,
+    pub crc: u32,
+    pub is_critical: bool,
+    pub is_public: bool,
+    pub is_safe_to_copy: bool,
+    pub is_reserved_bit_valid: bool,
+    pub is_valid_crc: bool,
+}
+
+impl ChunkMeta {
+    /// The summary of `chunk`, found at `index` and starting `offset` bytes
+    /// into the file
+    pub fn new(index: usize, offset: usize, chunk: &Chunk) -> Self {
+        let chunk_type = chunk.chunk_type();
+        Self {
+            index,
+            chunk_type: chunk_type.to_string(),
+            offset,
+            length: chunk.length(),
+            crc: chunk.crc(),
+            is_critical: chunk_type.is_critical(),
+            is_public: chunk_type.is_public(),
+            is_safe_to_copy: chunk_type.is_safe_to_copy(),
+            is_reserved_bit_valid: chunk_type.is_reserved_bit_valid(),
+            is_valid_crc: chunk.is_crc_valid(),
+        }
+    }
+}
+
+impl Display for ChunkMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = if self.is_critical {
+            "critical"
+        } else {
+            "ancillary"
+        };
+        let visibility = if self.is_public { "public" } else { "private" };
+        let copying = if self.is_safe_to_copy {
+            "safe-to-copy"
+        } else {
+            "unsafe-to-copy"
+        };
+        write!(
+            f,
+            "{:>4}  {}  {kind:<9}  {visibility:<7}  {copying:<14}  offset {:>8}  length {:>8}  crc {:08x}",
+            self.index, self.chunk_type, self.offset, self.length, self.crc
+        )?;
+        if !self.is_valid_crc {
+            write!(f, " (invalid)")?;
+        }
+        if !self.is_reserved_bit_valid {
+            write!(f, " (reserved bit set)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The critical chunk types defined by the PNG specification
+const KNOWN_CRITICAL: [&str; 4] = ["IHDR", "PLTE", "IDAT", "IEND"];
+
+/// The critical chunk types a file may hold at most one of
+const UNIQUE_CRITICAL: [&str; 3] = ["IHDR", "PLTE", "IEND"];
+
+/// A problem found by `Png::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The chunk's stored CRC doesn't match its type and data
+    BadCrc { index: usize, chunk_type: String },
+    /// The last chunk isn't IEND; `None` when there are no chunks
+    IendNotLast { found: Option<String> },
+    /// IEND holds data
+    IendNotEmpty { length: u32 },
+    /// A critical chunk follows the first IEND
+    CriticalAfterIend { index: usize, chunk_type: String },
+    /// A critical chunk of a type decoders don't know, so they can't safely
+    /// render the image
+    UnknownCritical { index: usize, chunk_type: String },
+    /// Bytes after IEND that don't parse as chunks
+    TrailingBytes { length: usize },
+    /// One of the `validate_*` checks failed, with its message
+    Structure(String),
+}
+
+impl ValidationError {
+    /// Whether this is only a warning, which `verify --strict` treats as an
+    /// error
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            Self::UnknownCritical { .. } | Self::TrailingBytes { .. }
+        )
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadCrc { index, chunk_type } => {
+                write!(f, "Chunk {index} ({chunk_type}) has an invalid CRC")
+            }
+            Self::IendNotLast { found: Some(found) } => {
+                write!(f, "expected IEND as the last chunk, found '{found}'")
+            }
+            Self::IendNotLast { found: None } => {
+                write!(f, "expected IEND as the last chunk, found no chunks")
+            }
+            Self::IendNotEmpty { length } => {
+                write!(f, "IEND must be empty, found {length} bytes of data")
+            }
+            Self::CriticalAfterIend { index, chunk_type } => {
+                write!(
+                    f,
+                    "critical chunk {chunk_type} at index {index} comes after IEND"
+                )
+            }
+            Self::UnknownCritical { index, chunk_type } => {
+                write!(f, "unknown critical chunk {chunk_type} at index {index}")
+            }
+            Self::TrailingBytes { length } => {
+                write!(f, "{length} bytes after IEND that are not chunks")
+            }
+            Self::Structure(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// A region skipped by the lenient parser to get back onto a chunk boundary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resync {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Where and why a strict parse of a file stops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    pub offset: usize,
+    pub message: String,
+    /// Hex dump of the bytes around `offset`, the byte at `offset` in brackets
+    pub context: String,
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        write!(f, "    {}", self.context)
+    }
+}
+
+impl ParseDiagnostic {
+    fn new(bytes: &[u8], offset: usize, message: String) -> Self {
+        let start = offset.saturating_sub(8).min(bytes.len());
+        let end = (offset + 8).min(bytes.len());
+        let mut context = (start..end)
+            .map(|i| match i == offset {
+                true => format!("[{:02x}]", bytes[i]),
+                false => format!("{:02x}", bytes[i]),
+            })
+            .collect::<Vec<_>>();
+        if offset >= bytes.len() {
+            context.push("[EOF]".to_string());
+        }
+        Self {
+            offset,
+            context: format!("{start:08x}: {}", context.join(" ")),
+            message,
+        }
+    }
 }
 
 impl TryFrom<&[u8]> for Png {
     type Error = super::Error;
 
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        if &value[..8] != Self::STANDARD_HEADER {
-            return Err("Incorrect Header".into())
-        }
-        let mut i = 8;
-        let mut chunks = Vec::new();
-        while i < value.len() {
-            let chunk = Chunk::try_from(&value[i..])?;
-            // 4 bytes from length, 4 bytes from chunk_type, 4 bytes from crc, rest from data
-            let chunk_length = chunk.as_bytes().len();
-            i += chunk_length as usize;
-            chunks.push(chunk);
-        }
-        Ok(Self { chunks })
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Self::check_signature(value)?;
+        Self::parse_chunks(&value[8..], 8)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Png {
+    type Error = super::Error;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(&value[..])
+    }
+}
+
+/// Iterates over the chunks in file order, like `Png::chunks`
+///
+/// ```
+/// use std::str::FromStr;
+/// use pngme::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+///
+/// let chunk = |chunk_type: &str, data: &str| {
+///     Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.into())
+/// };
+/// let png = Png::from_chunks(vec![
+///     chunk("ruSt", "one"),
+///     chunk("tEXt", "Title\0pngme"),
+///     chunk("ruSt", "two"),
+/// ]);
+///
+/// let mut messages = 0;
+/// for chunk in &png {
+///     if chunk.chunk_type().to_string() == "ruSt" {
+///         messages += 1;
+///     }
+/// }
+/// assert_eq!(messages, 2);
+/// ```
+impl<'a> IntoIterator for &'a Png {
+    type Item = &'a Chunk;
+    type IntoIter = std::slice::Iter<'a, Chunk>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.iter()
+    }
+}
+
+impl Display for Png {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display_with(&Interpreters::default()).fmt(f)
+    }
+}
+
+/// How the bytes of a serialized `Png` are split between chunk data and the
+/// rest; see `Png::size_summary`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeSummary {
+    pub total: usize,
+    pub chunks: usize,
+    pub critical_data: usize,
+    pub ancillary_data: usize,
+    /// The signature plus each chunk's length, type and CRC
+    pub overhead: usize,
+    pub trailing: usize,
+}
+
+impl Display for SizeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Size: {} bytes in {} chunks ({} critical data, {} ancillary data, {} overhead",
+            self.total, self.chunks, self.critical_data, self.ancillary_data, self.overhead
+        )?;
+        if self.trailing > 0 {
+            write!(f, ", {} trailing", self.trailing)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Displays a `Png` like its `Display` impl, decoding chunks with the given
+/// interpreters; see `Png::display_with`
+pub struct PngDisplay<'a> {
+    png: &'a Png,
+    interpreters: &'a Interpreters,
+    shown: usize,
+    size: SizeSummary,
+}
+
+impl PngDisplay<'_> {
+    /// Hexdumps up to `shown` bytes of each chunk's data instead of
+    /// `Chunk::PREVIEW_BYTES`
+    pub fn showing(self, shown: usize) -> Self {
+        Self { shown, ..self }
+    }
+    /// Reports `size` instead of the displayed `Png`'s own size, for when
+    /// only some chunks of the file are shown
+    pub fn sized(self, size: SizeSummary) -> Self {
+        Self { size, ..self }
+    }
+}
+
+impl Display for PngDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Header: {:?}", self.png.header())?;
+        writeln!(f, "{}", self.size)?;
+        let ihdr = self.png.ihdr();
+        if let Some(ihdr) = ihdr {
+            writeln!(f, "Image: {ihdr}")?;
+        }
+        for chunk in &self.png.chunks {
+            let shown = chunk.display_showing(self.shown);
+            match f.width() {
+                Some(width) => write!(f, "{shown:width$}")?,
+                None => write!(f, "{shown}")?,
+            }
+            if f.alternate() {
+                writeln!(f, "    about: {}", chunk.chunk_type().description())?;
+            }
+            if chunk.is_data_skipped() {
+                continue;
+            }
+            match self.interpreters.interpret(
+                chunk.chunk_type().bytes(),
+                chunk.data(),
+                ihdr.map(|ihdr| ihdr.color_type),
+            ) {
+                Some(Ok(meaning)) => writeln!(f, "    decoded: {meaning}")?,
+                Some(Err(e)) => writeln!(f, "    decoded: invalid ({e})")?,
+                None => {}
+            }
+        }
+        if !self.png.trailing.is_empty() {
+            writeln!(f, "{} trailing bytes after IEND", self.png.trailing.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl Png {
+    pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+    pub const SEAL_CHUNK_TYPE: &'static str = "shHa";
+    /// Holds a file stored by `stash`: a header chunk, then the contents
+    pub const STASH_CHUNK_TYPE: &'static str = "stSh";
+    /// Starts the stash header chunk, followed by the file's length (8 bytes,
+    /// big-endian) and its name as UTF-8
+    pub const STASH_MAGIC: &'static [u8; 4] = b"PMST";
+    const EMPTY_FILE: &'static str = "file is empty (0 bytes), not a PNG";
+
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
+        Self {
+            chunks,
+            trailing: Vec::new(),
+        }
+    }
+    /// Parses chunks starting at the first byte, for chunk streams dumped
+    /// without the 8-byte signature. Anything after IEND is kept as
+    /// `trailing_bytes`.
+    pub fn from_chunk_stream(value: &[u8]) -> super::Result<Png> {
+        Self::parse_chunks(value, 0)
+    }
+    /// `from_chunk_stream` for a stream found `base_offset` bytes into the
+    /// file, which traced offsets count from
+    fn parse_chunks(value: &[u8], base_offset: usize) -> super::Result<Png> {
+        let mut i = 0;
+        let mut chunks = Vec::new();
+        while i < value.len() && !Self::ends_with_iend(&chunks) {
+            trace::chunk_header(&value[i..], base_offset + i);
+            let chunk = Chunk::try_from(&value[i..])?;
+            // 4 bytes from length, 4 bytes from chunk_type, 4 bytes from crc, rest from data
+            let chunk_length = chunk.serialized_len();
+            i += chunk_length;
+            chunks.push(chunk);
+        }
+        Ok(Self {
+            chunks,
+            trailing: value[i..].to_vec(),
+        })
+    }
+    fn ends_with_iend(chunks: &[Chunk]) -> bool {
+        chunks
+            .last()
+            .is_some_and(|chunk| chunk.chunk_type().to_string() == "IEND")
+    }
+    /// The bytes that followed the IEND chunk in the parsed file, which some
+    /// tools use to hide data
+    pub fn trailing_bytes(&self) -> &[u8] {
+        &self.trailing
+    }
+    /// Checks for the 8-byte PNG signature before any chunk is parsed
+    fn check_signature(bytes: &[u8]) -> super::Result<()> {
+        if bytes.is_empty() {
+            return Err(PngError::Malformed(Self::EMPTY_FILE.to_string()));
+        }
+        if bytes.get(..8) != Some(&Self::STANDARD_HEADER[..]) {
+            return Err(PngError::InvalidSignature);
+        }
+        Ok(())
+    }
+    /// Displays the file with `interpreters` decoding chunk data as well as
+    /// the built-in ones
+    pub fn display_with<'a>(&'a self, interpreters: &'a Interpreters) -> PngDisplay<'a> {
+        PngDisplay {
+            png: self,
+            interpreters,
+            shown: Chunk::PREVIEW_BYTES,
+            size: self.size_summary(),
+        }
+    }
+    /// Walks the file the way the strict parser does and explains the first
+    /// place it would fail, or returns `None` if the file parses
+    pub fn diagnose(bytes: &[u8]) -> Option<ParseDiagnostic> {
+        let fail =
+            |offset: usize, message: String| Some(ParseDiagnostic::new(bytes, offset, message));
+        let read_u32 =
+            |offset: usize| u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        if bytes.len() < 8 {
+            return fail(
+                bytes.len(),
+                format!(
+                    "expected 8-byte PNG signature at offset 0, got EOF after {} bytes",
+                    bytes.len()
+                ),
+            );
+        }
+        if bytes[..8] != Self::STANDARD_HEADER {
+            return fail(
+                0,
+                format!("expected PNG signature at offset 0, got {:?}", &bytes[..8]),
+            );
+        }
+        let mut i = 8;
+        while i < bytes.len() {
+            if bytes.len() < i + 4 {
+                return fail(
+                    bytes.len(),
+                    format!("expected chunk length at offset {i}, got EOF"),
+                );
+            }
+            let length = read_u32(i) as usize;
+            if bytes.len() < i + 8 {
+                return fail(
+                    bytes.len(),
+                    format!("expected chunk type at offset {}, got EOF", i + 4),
+                );
+            }
+            let type_bytes = &bytes[i + 4..i + 8];
+            if !type_bytes.iter().all(u8::is_ascii_alphabetic) {
+                return fail(
+                    i + 4,
+                    format!(
+                        "expected chunk type of 4 ASCII letters at offset {}, got {type_bytes:?}",
+                        i + 4
+                    ),
+                );
+            }
+            let data_end = i + 8 + length;
+            if bytes.len() < data_end {
+                return fail(
+                    bytes.len(),
+                    format!(
+                        "expected {length} bytes of chunk data at offset {}, got EOF after {}",
+                        i + 8,
+                        bytes.len() - (i + 8)
+                    ),
+                );
+            }
+            if bytes.len() < data_end + 4 {
+                return fail(
+                    bytes.len(),
+                    format!("expected CRC at offset {data_end}, got EOF"),
+                );
+            }
+            let expected = CRC.checksum(&bytes[i + 4..data_end]);
+            let found = read_u32(data_end);
+            if expected != found {
+                return fail(
+                    data_end,
+                    format!(
+                        "expected CRC {expected:#010x} at offset {data_end}, got {found:#010x}"
+                    ),
+                );
+            }
+            if type_bytes == b"IEND" {
+                // The parser keeps anything after IEND as trailing bytes
+                return None;
+            }
+            i = data_end + 4;
+        }
+        None
+    }
+    /// Parses the file like `try_from` when `strict`, or otherwise like
+    /// `try_from_keeping_bad_crcs` so chunks with a bad CRC can be inspected
+    /// or repaired
+    pub fn try_from_bytes(bytes: &[u8], strict: bool) -> super::Result<Png> {
+        match strict {
+            true => Self::try_from(bytes),
+            false => Self::try_from_keeping_bad_crcs(bytes),
+        }
+    }
+    /// Parses the file like `try_from` but keeps chunks with a bad CRC,
+    /// storing the CRC from the file so `Chunk::is_crc_valid` reports it
+    pub fn try_from_keeping_bad_crcs(value: &[u8]) -> super::Result<Png> {
+        Self::check_signature(value)?;
+        let (chunks, end) = Self::chunks_keeping_bad_crcs(value, 8, true)?;
+        Ok(Self {
+            chunks,
+            trailing: value[end..].to_vec(),
+        })
+    }
+    /// The chunks of `value` from offset `start`, up to IEND if
+    /// `stop_at_iend` or else to the end, and the offset where they stop
+    fn chunks_keeping_bad_crcs(
+        value: &[u8],
+        start: usize,
+        stop_at_iend: bool,
+    ) -> super::Result<(Vec<Chunk>, usize)> {
+        let mut i = start;
+        let mut chunks = Vec::new();
+        while i < value.len() && !(stop_at_iend && Self::ends_with_iend(&chunks)) {
+            trace::chunk_header(&value[i..], i);
+            if !Self::is_plausible_chunk(&value[i..]) {
+                return Err(format!("Truncated or malformed chunk at offset {i}").into());
+            }
+            let length = u32::from_be_bytes(value[i..i + 4].try_into().unwrap()) as usize;
+            let chunk_type =
+                ChunkType::try_from(<[u8; 4]>::try_from(&value[i + 4..i + 8]).unwrap())?;
+            let data = value[i + 8..i + 8 + length].to_vec();
+            let crc_at = i + 8 + length;
+            let crc = u32::from_be_bytes(value[crc_at..crc_at + 4].try_into().unwrap());
+            chunks.push(Chunk::with_stored_crc(chunk_type, data, crc)?);
+            i = crc_at + 4;
+        }
+        Ok((chunks, i))
+    }
+    /// Checks the signature and returns a reader for the chunks after it, for
+    /// going through a file too large to load at once
+    pub fn read_chunks<R: Read>(mut reader: R) -> super::Result<ChunkReader<R>> {
+        let mut header = Vec::new();
+        reader.by_ref().take(8).read_to_end(&mut header)?;
+        if header.is_empty() {
+            return Err(PngError::Malformed(Self::EMPTY_FILE.to_string()));
+        }
+        if header != Self::STANDARD_HEADER {
+            return Err(PngError::InvalidSignature);
+        }
+        Ok(ChunkReader::new(reader))
+    }
+    /// Parses as many chunks as possible, skipping over regions that don't
+    /// parse by scanning forward for the next plausible chunk boundary
+    pub fn try_from_lenient(value: &[u8]) -> super::Result<(Png, Vec<Resync>)> {
+        Self::check_signature(value)?;
+        let mut i = 8;
+        let mut chunks = Vec::new();
+        let mut resyncs = Vec::new();
+        while i < value.len() && !Self::ends_with_iend(&chunks) {
+            if Self::is_plausible_chunk(&value[i..]) {
+                if let Ok(chunk) = Chunk::try_from(&value[i..]) {
+                    i += chunk.serialized_len();
+                    chunks.push(chunk);
+                    continue;
+                }
+            }
+            let from = i;
+            i = (i + 1..value.len())
+                .find(|&j| Self::is_plausible_chunk(&value[j..]))
+                .unwrap_or(value.len());
+            resyncs.push(Resync { from, to: i });
+        }
+        let trailing = value[i..].to_vec();
+        Ok((Self { chunks, trailing }, resyncs))
+    }
+    /// Reads every chunk except IDAT, whose data is seeked over and kept as a
+    /// skipped placeholder holding only the length and stored CRC
+    pub fn metadata_from_seekable<R: Read + Seek>(reader: &mut R) -> super::Result<Png> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if header != Self::STANDARD_HEADER {
+            return Err(PngError::InvalidSignature);
+        }
+        let mut chunks = Vec::new();
+        let mut trailing = Vec::new();
+        loop {
+            if Self::ends_with_iend(&chunks) {
+                reader.read_to_end(&mut trailing)?;
+                break;
+            }
+            let mut length = [0; 4];
+            match reader.read_exact(&mut length) {
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                result => result?,
+            }
+            let length = u32::from_be_bytes(length);
+            let mut chunk_type = [0; 4];
+            reader.read_exact(&mut chunk_type)?;
+            let chunk_type = ChunkType::try_from(chunk_type)?;
+            let mut data = Vec::new();
+            if chunk_type.to_string() == "IDAT" {
+                reader.seek(SeekFrom::Current(length.into()))?;
+            } else {
+                reader.by_ref().take(length.into()).read_to_end(&mut data)?;
+                if data.len() != length as usize {
+                    return Err("Unexpected end of file in chunk data".into());
+                }
+            }
+            let mut crc = [0; 4];
+            reader.read_exact(&mut crc)?;
+            let crc = u32::from_be_bytes(crc);
+            if chunk_type.to_string() == "IDAT" {
+                chunks.push(Chunk::skipped(chunk_type, length, crc));
+            } else if Chunk::calculate_crc(&chunk_type, &data) == crc {
+                chunks.push(Chunk::new(chunk_type, data));
+            } else {
+                return Err(PngError::CrcMismatch {
+                    expected: Chunk::calculate_crc(&chunk_type, &data),
+                    found: crc,
+                });
+            }
+        }
+        Ok(Self { chunks, trailing })
+    }
+    /// Copies the data of every chunk of the given type from a PNG stream to
+    /// `writer` in fixed-size pieces, so the data is never held in memory all at
+    /// once. Data is written before its chunk's CRC has been checked, so on a
+    /// CRC error the writer holds partial output. Reading stops after IEND,
+    /// so trailing bytes are ignored. Returns the number of bytes written.
+    pub fn stream_chunk_data<R: Read, W: Write>(
+        reader: &mut R,
+        chunk_type: &str,
+        writer: &mut W,
+    ) -> super::Result<u64> {
+        let wanted = |t: &ChunkType| t.to_string() == chunk_type;
+        Self::stream_chunk_data_where(reader, wanted, writer)?
+            .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))
+    }
+    /// Like `stream_chunk_data`, but copies the chunks, in file order, for
+    /// which `wanted` returns true. Returns `None` if none did.
+    pub fn stream_chunk_data_where<R: Read, W: Write>(
+        reader: &mut R,
+        mut wanted: impl FnMut(&ChunkType) -> bool,
+        writer: &mut W,
+    ) -> super::Result<Option<u64>> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if header != Self::STANDARD_HEADER {
+            return Err(PngError::InvalidSignature);
+        }
+        let mut found = false;
+        let mut written = 0;
+        let mut offset = 8;
+        let mut buf = [0; 8192];
+        // Like `ChunkReader`: input may end between chunks, and anything
+        // after IEND is left unread
+        loop {
+            let truncated = |e: std::io::Error| match e.kind() {
+                ErrorKind::UnexpectedEof => {
+                    PngError::Malformed(format!("Truncated chunk at offset {offset}"))
+                }
+                _ => PngError::Io(e),
+            };
+            let mut length = [0; 4];
+            let mut filled = 0;
+            while filled < 4 {
+                match reader.read(&mut length[filled..]) {
+                    Ok(0) if filled == 0 => return Ok(found.then_some(written)),
+                    Ok(0) => return Err(truncated(ErrorKind::UnexpectedEof.into())),
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            let length = u32::from_be_bytes(length) as usize;
+            let mut remaining = length;
+            let mut type_bytes = [0; 4];
+            reader.read_exact(&mut type_bytes).map_err(truncated)?;
+            let chunk_type = ChunkType::try_from(type_bytes)?;
+            let matches = wanted(&chunk_type);
+            let mut digest = CRC.digest();
+            digest.update(&type_bytes);
+            while remaining > 0 {
+                let piece = &mut buf[..remaining.min(8192)];
+                reader.read_exact(piece).map_err(truncated)?;
+                digest.update(piece);
+                if matches {
+                    writer.write_all(piece)?;
+                    written += piece.len() as u64;
+                }
+                remaining -= piece.len();
+            }
+            let mut crc = [0; 4];
+            reader.read_exact(&mut crc).map_err(truncated)?;
+            let (expected, stored) = (digest.finalize(), u32::from_be_bytes(crc));
+            if expected != stored {
+                return Err(PngError::CrcMismatch {
+                    expected,
+                    found: stored,
+                });
+            }
+            found |= matches;
+            offset += 12 + length;
+            if chunk_type.to_string() == "IEND" {
+                return Ok(found.then_some(written));
+            }
+        }
+    }
+    fn is_plausible_chunk(value: &[u8]) -> bool {
+        if value.len() < 12 {
+            return false;
+        }
+        let length = u32::from_be_bytes(value[..4].try_into().unwrap()) as usize;
+        value[4..8].iter().all(u8::is_ascii_alphabetic) && length <= value.len() - 12
+    }
+    /// Adds a chunk just before IEND, keeping the file valid, or at the end
+    /// when there is no IEND
+    pub fn append_chunk(&mut self, chunk: Chunk) {
+        let index = self.append_position();
+        self.chunks.insert(index, chunk);
+    }
+    /// Like `append_chunk`, but refuses a second IHDR, PLTE or IEND
+    pub fn try_append_chunk(&mut self, chunk: Chunk) -> super::Result<()> {
+        self.check_unique_critical(&chunk)?;
+        self.append_chunk(chunk);
+        Ok(())
+    }
+    fn check_unique_critical(&self, chunk: &Chunk) -> super::Result<()> {
+        let chunk_type = chunk.chunk_type().to_string();
+        if UNIQUE_CRITICAL.contains(&chunk_type.as_str())
+            && self.chunk_by_type(&chunk_type).is_some()
+        {
+            return Err(format!(
+                "The file already has an {chunk_type} chunk, and only one is allowed"
+            )
+            .into());
+        }
+        Ok(())
+    }
+    /// Where `append_chunk` puts a chunk: before the last IEND, or at the end
+    /// when there is none
+    pub fn append_position(&self) -> usize {
+        self.chunks
+            .iter()
+            .rposition(|chunk| chunk.chunk_type().to_string() == "IEND")
+            .unwrap_or(self.chunks.len())
+    }
+    /// Indices where an ancillary chunk with no ordering constraints can be
+    /// inserted: after IHDR, up to IEND, and never inside the run of IDATs
+    pub fn ancillary_insert_positions(&self) -> Vec<usize> {
+        let is_type = |index: usize, chunk_type: &str| {
+            self.chunks
+                .get(index)
+                .is_some_and(|chunk| chunk.chunk_type().to_string() == chunk_type)
+        };
+        let end = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == "IEND")
+            .unwrap_or(self.chunks.len());
+        let start = usize::from(is_type(0, "IHDR"));
+        (start..=end)
+            .filter(|&index| index == 0 || !(is_type(index - 1, "IDAT") && is_type(index, "IDAT")))
+            .collect()
+    }
+    /// Inserts a chunk before the one at `index`, or at the end when `index`
+    /// is the number of chunks. Index 0 puts it before IHDR, which leaves the
+    /// file invalid.
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) -> super::Result<()> {
+        if index > self.chunks.len() {
+            return Err(format!(
+                "Index {index} is out of range for {} chunks",
+                self.chunks.len()
+            )
+            .into());
+        }
+        self.chunks.insert(index, chunk);
+        Ok(())
+    }
+    /// Like `insert_chunk`, but refuses a second IHDR, PLTE or IEND like
+    /// `try_append_chunk`, and any position before IHDR or after IEND
+    pub fn try_insert_chunk(&mut self, index: usize, chunk: Chunk) -> super::Result<()> {
+        if index > self.chunks.len() {
+            return self.insert_chunk(index, chunk);
+        }
+        self.check_unique_critical(&chunk)?;
+        let starts_with_ihdr = self
+            .chunks
+            .first()
+            .is_some_and(|chunk| chunk.chunk_type().to_string() == "IHDR");
+        if index == 0 && starts_with_ihdr {
+            return Err("Index 0 is before IHDR, which must be the first chunk".into());
+        }
+        let iend = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == "IEND");
+        if iend.is_some_and(|iend| index > iend) {
+            return Err(
+                format!("Index {index} is after IEND, which must be the last chunk").into(),
+            );
+        }
+        self.insert_chunk(index, chunk)
+    }
+    /// Appends a chunk only if it can legally follow the existing chunks
+    pub fn append_chunk_checked(&mut self, chunk: Chunk) -> super::Result<()> {
+        let new_type = chunk.chunk_type().to_string();
+        let has = |chunk_type: &str| self.chunk_by_type(chunk_type).is_some();
+        let last_type = self
+            .chunks
+            .last()
+            .map(|chunk| chunk.chunk_type().to_string());
+        match (last_type.as_deref(), new_type.as_str()) {
+            (None, "IHDR") => {}
+            (None, _) => return Err(format!("{new_type} cannot come before IHDR").into()),
+            (Some("IEND"), _) => return Err(format!("{new_type} cannot come after IEND").into()),
+            (Some(_), "IHDR") => return Err("IHDR must be the first chunk".into()),
+            (Some(last), "IDAT") if last != "IDAT" && has("IDAT") => {
+                return Err("IDAT chunks must be consecutive".into())
+            }
+            (_, "PLTE") if has("PLTE") => return Err("Only one PLTE chunk is allowed".into()),
+            (_, "PLTE" | "tRNS" | "bKGD" | "hIST" | "pHYs" | "sPLT") if has("IDAT") => {
+                return Err(format!("{new_type} must come before IDAT").into())
+            }
+            (_, "cHRM" | "gAMA" | "iCCP" | "sBIT" | "sRGB") if has("PLTE") || has("IDAT") => {
+                return Err(format!("{new_type} must come before PLTE and IDAT").into())
+            }
+            (_, "IEND") if !has("IDAT") => {
+                return Err("IEND cannot come before any IDAT chunk".into())
+            }
+            _ => {}
+        }
+        self.chunks.push(chunk);
+        Ok(())
+    }
+    pub fn remove_chunk(&mut self, chunk_type: &str) -> super::Result<Chunk> {
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == chunk_type)
+            .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        Ok(self.chunks.remove(index))
+    }
+    pub fn remove_chunk_at(&mut self, index: usize) -> super::Result<Chunk> {
+        if index >= self.chunks.len() {
+            return Err(PngError::ChunkNotFound(format!("@{index}")));
+        }
+        Ok(self.chunks.remove(index))
+    }
+    /// Removes every chunk of the given type, returning how many there were
+    pub fn remove_all_chunks(&mut self, chunk_type: &str) -> usize {
+        self.remove_chunks_where(|chunk| chunk.chunk_type().to_string() == chunk_type)
+            .len()
+    }
+    /// Removes every chunk matching the predicate, returning them in file order
+    pub fn remove_chunks_where<F: Fn(&Chunk) -> bool>(&mut self, predicate: F) -> Vec<Chunk> {
+        let (removed, kept) = self.chunks.drain(..).partition(|chunk| predicate(chunk));
+        self.chunks = kept;
+        removed
+    }
+    /// Swaps the data of the first chunk of the type, updating its length and
+    /// CRC but keeping its position
+    pub fn replace_chunk_data(&mut self, chunk_type: &str, data: Vec<u8>) -> super::Result<()> {
+        let chunk = self
+            .chunks
+            .iter_mut()
+            .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
+            .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        chunk.set_data(data);
+        Ok(())
+    }
+    /// Removes every ancillary chunk, keeping only critical ones such as
+    /// IHDR, PLTE, IDAT and IEND. Returns how many chunks were removed.
+    pub fn retain_critical(&mut self) -> usize {
+        self.remove_chunks_where(|chunk| !chunk.chunk_type().is_critical())
+            .len()
+    }
+    /// Removes ancillary chunks with the same type and data as an earlier
+    /// chunk, returning how many were removed
+    pub fn remove_duplicate_ancillary(&mut self) -> usize {
+        let mut seen = HashSet::new();
+        let duplicates = self
+            .chunks
+            .iter()
+            .map(|chunk| {
+                !chunk.chunk_type().is_critical()
+                    && !seen.insert((chunk.chunk_type().to_string(), chunk.data()))
+            })
+            .collect::<Vec<_>>();
+        let mut duplicate = duplicates.iter();
+        self.chunks.retain(|_| !duplicate.next().unwrap());
+        duplicates.into_iter().filter(|&d| d).count()
+    }
+    /// Removes chunks other than IEND that hold no data, returning how many
+    /// were removed
+    pub fn remove_empty_chunks(&mut self) -> usize {
+        self.remove_chunks_where(|chunk| {
+            chunk.length() == 0 && chunk.chunk_type().to_string() != "IEND"
+        })
+        .len()
+    }
+    pub fn header(&self) -> &[u8; 8] {
+        &Self::STANDARD_HEADER
+    }
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+    pub fn chunks_mut(&mut self) -> &mut [Chunk] {
+        &mut self.chunks
+    }
+    /// Fixes every chunk's stored CRC, returning how many were wrong
+    pub fn recompute_crcs(&mut self) -> usize {
+        self.chunks
+            .iter_mut()
+            .map(Chunk::recompute_crc)
+            .filter(|&fixed| fixed)
+            .count()
+    }
+    /// Renames every chunk of each `(from, to)` type, returning how many chunks
+    /// each pair renamed. Each chunk is renamed at most once, by the first
+    /// pair matching its original type.
+    pub fn rename_chunks(&mut self, renames: &[(ChunkType, ChunkType)]) -> Vec<usize> {
+        let mut counts = vec![0; renames.len()];
+        for chunk in &mut self.chunks {
+            if let Some(i) = renames
+                .iter()
+                .position(|(from, _)| chunk.chunk_type() == from)
+            {
+                chunk.set_chunk_type(renames[i].1);
+                counts[i] += 1;
+            }
+        }
+        counts
+    }
+    /// Chunks with a private (lowercase second letter) type, with their indices
+    pub fn private_chunks(&self) -> Vec<(usize, &Chunk)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| !chunk.chunk_type().is_public())
+            .collect()
+    }
+    /// A copy holding only the last `n` chunks, plus IEND if it isn't one of them
+    pub fn tail(&self, n: usize) -> Png {
+        let mut chunks = self.chunks[self.chunks.len().saturating_sub(n)..].to_vec();
+        let has_iend = chunks
+            .iter()
+            .any(|chunk| chunk.chunk_type().to_string() == "IEND");
+        if let (false, Some(iend)) = (has_iend, self.chunk_by_type("IEND")) {
+            chunks.push(iend.clone());
+        }
+        Self {
+            chunks,
+            trailing: self.trailing.clone(),
+        }
+    }
+    /// The chunk at `index` in file order, counting from IHDR at 0
+    pub fn chunk_at(&self, index: usize) -> Option<&Chunk> {
+        self.chunks.get(index)
+    }
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
+        self.find_chunk(|chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+    /// The first chunk, in file order, for which `predicate` holds
+    pub fn find_chunk<F: Fn(&Chunk) -> bool>(&self, predicate: F) -> Option<&Chunk> {
+        self.chunks.iter().find(|chunk| predicate(chunk))
+    }
+    /// Every chunk of the given type, in file order
+    pub fn chunks_by_type(&self, chunk_type: &str) -> Vec<&Chunk> {
+        self.chunks_matching(chunk_type, false)
+    }
+    /// Every chunk of the type, optionally ignoring the ASCII case of its
+    /// letters (and so the properties they encode); see `Chunk::has_type`
+    pub fn chunks_matching(&self, chunk_type: &str, ignore_case: bool) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.has_type(chunk_type, ignore_case))
+            .collect()
+    }
+    /// How many chunks of each type the file has
+    pub fn chunk_type_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for chunk in &self.chunks {
+            *counts.entry(chunk.chunk_type().to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
+    /// Replaces every IDAT chunk with a single one holding their data
+    /// concatenated in file order, where the first IDAT was. Returns how many
+    /// IDAT chunks there were.
+    pub fn merge_idat(&mut self) -> usize {
+        let is_idat = |chunk: &Chunk| chunk.chunk_type().to_string() == "IDAT";
+        let count = self.chunks.iter().filter(|chunk| is_idat(chunk)).count();
+        let Some(first) = self.chunks.iter().position(is_idat).filter(|_| count > 1) else {
+            return count;
+        };
+        let data = self
+            .chunks
+            .iter()
+            .filter(|chunk| is_idat(chunk))
+            .flat_map(|chunk| chunk.data().iter().copied())
+            .collect();
+        self.chunks.retain(|chunk| !is_idat(chunk));
+        let chunk_type = ChunkType::from_str("IDAT").unwrap();
+        self.chunks.insert(first, Chunk::new(chunk_type, data));
+        count
+    }
+    /// Re-splits the concatenated IDAT data, unchanged, into IDAT chunks of
+    /// at most `size` bytes where the first IDAT was. Returns how many IDAT
+    /// chunks there are now.
+    pub fn split_idat(&mut self, size: usize) -> super::Result<usize> {
+        if size == 0 {
+            return Err("IDAT chunk size must be at least 1".into());
+        }
+        if self.merge_idat() == 0 {
+            return Ok(0);
+        }
+        let first = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == "IDAT")
+            .unwrap();
+        let merged = self.chunks.remove(first);
+        let pieces = merged
+            .data()
+            .chunks(size)
+            .map(|piece| Chunk::new(ChunkType::from_str("IDAT").unwrap(), piece.to_vec()))
+            .collect::<Vec<_>>();
+        let count = pieces.len();
+        self.chunks.splice(first..first, pieces);
+        Ok(count)
+    }
+    /// Replaces the gAMA chunk's value, or inserts one before PLTE and IDAT
+    pub fn set_gamma(&mut self, gamma: Gamma) -> super::Result<()> {
+        let data = gamma.to_bytes().to_vec();
+        if let Some(chunk) = self
+            .chunks
+            .iter_mut()
+            .find(|chunk| chunk.chunk_type().to_string() == "gAMA")
+        {
+            chunk.set_data(data);
+            return Ok(());
+        }
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| matches!(chunk.chunk_type().to_string().as_str(), "PLTE" | "IDAT"))
+            .ok_or("Can't place gAMA in a file without PLTE or IDAT")?;
+        self.chunks
+            .insert(index, Chunk::new(ChunkType::from_str("gAMA")?, data));
+        Ok(())
+    }
+    /// Every tEXt, zTXt and iTXt chunk, decoded, in file order
+    pub fn all_text(&self) -> super::Result<Vec<TextEntry>> {
+        self.chunks
+            .iter()
+            .filter(|chunk| {
+                matches!(
+                    chunk.chunk_type().to_string().as_str(),
+                    "tEXt" | "zTXt" | "iTXt"
+                )
+            })
+            .map(TextEntry::try_from)
+            .collect()
+    }
+    /// Every problem with the file's structure: bad CRCs, IHDR not first,
+    /// IEND not last or not empty, critical chunks after IEND (parsed from
+    /// the trailing bytes), unknown critical chunks and the other
+    /// `validate_*` checks. The signature is checked when parsing.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        self.validate_with_progress(|_, _| {})
+    }
+    /// Like `validate`, calling `progress` after each CRC with the number of
+    /// chunks checked so far and the number there are to check, which
+    /// includes chunks parsed from the trailing bytes
+    pub fn validate_with_progress(
+        &self,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Vec<ValidationError> {
+        // Chunks after IEND are kept as trailing bytes when parsing, so parse
+        // them again to check them like the rest
+        if !self.trailing.is_empty() {
+            if let Ok((after, _)) = Self::chunks_keeping_bad_crcs(&self.trailing, 0, false) {
+                let mut chunks = self.chunks.clone();
+                chunks.extend(after);
+                return Png::from_chunks(chunks).validate_with_progress(progress);
+            }
+        }
+        let mut errors = Vec::new();
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            if !chunk.is_crc_valid() {
+                errors.push(ValidationError::BadCrc {
+                    index,
+                    chunk_type: chunk.chunk_type().to_string(),
+                });
+            }
+            progress(index + 1, self.chunks.len());
+        }
+        let last = self
+            .chunks
+            .last()
+            .map(|chunk| chunk.chunk_type().to_string());
+        if last.as_deref() != Some("IEND") {
+            errors.push(ValidationError::IendNotLast { found: last });
+        }
+        let iend = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == "IEND");
+        if let Some(iend) = iend {
+            let length = self.chunks[iend].length();
+            if length > 0 {
+                errors.push(ValidationError::IendNotEmpty { length });
+            }
+            for (index, chunk) in self.chunks.iter().enumerate().skip(iend + 1) {
+                if chunk.chunk_type().is_critical() {
+                    errors.push(ValidationError::CriticalAfterIend {
+                        index,
+                        chunk_type: chunk.chunk_type().to_string(),
+                    });
+                }
+            }
+        }
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let chunk_type = chunk.chunk_type().to_string();
+            if chunk.chunk_type().is_critical() && !KNOWN_CRITICAL.contains(&chunk_type.as_str()) {
+                errors.push(ValidationError::UnknownCritical { index, chunk_type });
+            }
+        }
+        let checks = [
+            self.validate_ihdr_first(),
+            self.validate_ihdr(),
+            self.validate_unique_critical(),
+            self.validate_idat_contiguous(),
+            self.validate_palette(),
+        ];
+        errors.extend(
+            checks
+                .into_iter()
+                .filter_map(|result| result.err())
+                .map(|e| ValidationError::Structure(e.to_string())),
+        );
+        if !self.trailing.is_empty() {
+            errors.push(ValidationError::TrailingBytes {
+                length: self.trailing.len(),
+            });
+        }
+        errors
+    }
+    /// Checks that the first chunk is IHDR, pointing out a first chunk that
+    /// only differs in case
+    pub fn validate_ihdr_first(&self) -> super::Result<()> {
+        let Some(first) = self.chunks.first() else {
+            return Err("expected IHDR as the first chunk, found no chunks".into());
+        };
+        let found = first.chunk_type().to_string();
+        if found == "IHDR" {
+            Ok(())
+        } else if found.eq_ignore_ascii_case("IHDR") {
+            Err(format!(
+                "expected IHDR as the first chunk, found '{found}' — note PNG chunk type casing is significant"
+            )
+            .into())
+        } else {
+            Err(format!("expected IHDR as the first chunk, found '{found}'").into())
+        }
+    }
+    /// Checks that all IDAT chunks form one consecutive run
+    pub fn validate_idat_contiguous(&self) -> super::Result<()> {
+        let is_idat = |chunk: &Chunk| chunk.chunk_type().to_string() == "IDAT";
+        let (Some(first), Some(last)) = (
+            self.chunks.iter().position(is_idat),
+            self.chunks.iter().rposition(is_idat),
+        ) else {
+            return Ok(());
+        };
+        match (first..last).find(|&index| !is_idat(&self.chunks[index])) {
+            Some(index) => Err(format!(
+                "IDAT chunks are not contiguous: {} at index {index} interrupts them",
+                self.chunks[index].chunk_type()
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+    /// Checks that no critical chunk other than IDAT appears more than once
+    pub fn validate_unique_critical(&self) -> super::Result<()> {
+        for chunk_type in UNIQUE_CRITICAL {
+            let indices = self
+                .chunks
+                .iter()
+                .enumerate()
+                .filter(|(_, chunk)| chunk.chunk_type().to_string() == chunk_type)
+                .map(|(index, _)| index.to_string())
+                .collect::<Vec<_>>();
+            if indices.len() > 1 {
+                return Err(format!(
+                    "Duplicate {chunk_type} chunks at indices {}",
+                    indices.join(", ")
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+    /// Checks the fields of the IHDR chunk, if there is one; see
+    /// `IhdrInfo::validate`
+    pub fn validate_ihdr(&self) -> super::Result<()> {
+        match self.chunk_by_type("IHDR") {
+            Some(chunk) => IhdrInfo::try_from(chunk.data())?.validate(),
+            None => Ok(()),
+        }
+    }
+    /// Checks PLTE against the IHDR color type: indexed images need one and
+    /// grayscale images must not have one
+    pub fn validate_palette(&self) -> super::Result<()> {
+        let Some(ihdr) = self.ihdr() else {
+            return Ok(());
+        };
+        let has_palette = self.chunk_by_type("PLTE").is_some();
+        match ihdr.color_type {
+            3 if !has_palette => Err("Color type 3 (indexed) requires a PLTE chunk".into()),
+            0 | 4 if has_palette => Err(format!(
+                "PLTE chunk must not appear with grayscale color type {}",
+                ihdr.color_type
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+    pub fn ihdr(&self) -> Option<IhdrInfo> {
+        let chunk = self.chunk_by_type("IHDR")?;
+        IhdrInfo::try_from(chunk.data()).ok()
+    }
+    /// Every chunk as a JSON array of `Chunk::to_json` objects
+    pub fn to_json(&self) -> Json {
+        Json::Array(self.chunks.iter().map(Chunk::to_json).collect())
+    }
+    pub fn chunk_map(&self) -> Vec<ChunkMeta> {
+        let mut offset = Self::STANDARD_HEADER.len();
+        self.chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let meta = ChunkMeta::new(index, offset, chunk);
+                // The declared length, so chunks skipped by
+                // `metadata_from_seekable` still count their data
+                offset += 12 + chunk.length() as usize;
+                meta
+            })
+            .collect()
+    }
+    /// The size of the file `as_bytes` would produce, broken down by section
+    pub fn size_summary(&self) -> SizeSummary {
+        let (critical, ancillary): (Vec<&Chunk>, Vec<&Chunk>) = self
+            .chunks
+            .iter()
+            .partition(|chunk| chunk.chunk_type().is_critical());
+        let data_size = |chunks: Vec<&Chunk>| {
+            chunks
+                .iter()
+                .map(|chunk| chunk.length() as usize)
+                .sum::<usize>()
+        };
+        let critical_data = data_size(critical);
+        let ancillary_data = data_size(ancillary);
+        // 4 bytes of length, 4 of type and 4 of CRC per chunk
+        let overhead = Self::STANDARD_HEADER.len() + 12 * self.chunks.len();
+        let trailing = self.trailing.len();
+        SizeSummary {
+            total: critical_data + ancillary_data + overhead + trailing,
+            chunks: self.chunks.len(),
+            critical_data,
+            ancillary_data,
+            overhead,
+            trailing,
+        }
+    }
+    /// The `start..end` byte range of a chunk in the serialized file, from its
+    /// length field through its CRC
+    pub fn byte_range_of_chunk(&self, index: usize) -> Option<(usize, usize)> {
+        let chunk = self.chunks.get(index)?;
+        let start = Self::STANDARD_HEADER.len()
+            + self.chunks[..index]
+                .iter()
+                .map(|chunk| 12 + chunk.length() as usize)
+                .sum::<usize>();
+        Some((start, start + 12 + chunk.length() as usize))
+    }
+    /// SHA-256 over the serialized bytes (length, type, data and CRC) of every
+    /// chunk except seal chunks, in file order. The signature is not included.
+    pub fn seal_digest(&self) -> [u8; 32] {
+        let sealed = self
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() != Self::SEAL_CHUNK_TYPE)
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect::<Vec<u8>>();
+        sha256(&sealed)
+    }
+    /// Replaces any existing seal with one covering the current chunks
+    pub fn seal(&mut self) {
+        let digest = self.seal_digest();
+        self.chunks
+            .retain(|chunk| chunk.chunk_type().to_string() != Self::SEAL_CHUNK_TYPE);
+        let chunk_type = ChunkType::from_str(Self::SEAL_CHUNK_TYPE).unwrap();
+        self.append_chunk(Chunk::new(chunk_type, digest.to_vec()));
+    }
+    pub fn verify_seal(&self) -> super::Result<bool> {
+        let seal = self
+            .chunk_by_type(Self::SEAL_CHUNK_TYPE)
+            .ok_or("File is not sealed")?;
+        Ok(seal.data() == self.seal_digest())
+    }
+    /// Stores a whole file before IEND as a header chunk holding its name
+    /// and length, then its contents split into chunks of at most
+    /// `chunk_size` bytes. Returns how many chunks were added.
+    pub fn stash(
+        &mut self,
+        file_name: &str,
+        data: &[u8],
+        chunk_size: usize,
+    ) -> super::Result<usize> {
+        if self.chunk_by_type(Self::STASH_CHUNK_TYPE).is_some() {
+            return Err(format!(
+                "The file already holds a stashed file, remove its {} chunks first",
+                Self::STASH_CHUNK_TYPE
+            )
+            .into());
+        }
+        let chunk_type = ChunkType::from_str(Self::STASH_CHUNK_TYPE).unwrap();
+        let mut header = Self::STASH_MAGIC.to_vec();
+        header.extend((data.len() as u64).to_be_bytes());
+        header.extend(file_name.as_bytes());
+        let mut chunks = vec![Chunk::new(chunk_type, header)];
+        chunks.extend(Chunk::split_data(chunk_type, data, chunk_size)?);
+        let added = chunks.len();
+        for chunk in chunks {
+            self.append_chunk(chunk);
+        }
+        Ok(added)
+    }
+    /// The name and contents of a file stored by `stash`
+    pub fn unstash(&self) -> super::Result<(String, Vec<u8>)> {
+        let chunks = self.chunks_by_type(Self::STASH_CHUNK_TYPE);
+        let (header, pieces) = chunks.split_first().ok_or("File holds no stashed file")?;
+        let header = header
+            .data()
+            .strip_prefix(Self::STASH_MAGIC)
+            .ok_or("The first stash chunk is not a stash header")?;
+        let (length, name) = header
+            .split_first_chunk::<8>()
+            .ok_or("The stash header is too short")?;
+        let length = u64::from_be_bytes(*length);
+        let name = String::from_utf8(name.to_vec())?;
+        let data = Chunk::join_data(pieces)?;
+        if data.len() as u64 != length {
+            return Err(format!(
+                "Stashed file is incomplete: expected {length} bytes, found {}",
+                data.len()
+            )
+            .into());
+        }
+        Ok((name, data))
+    }
+    /// The whole file as a `data:image/png;base64,...` URI
+    pub fn data_uri(&self) -> String {
+        format!("data:image/png;base64,{}", base64_encode(&self.as_bytes()))
+    }
+    /// Writes the signature and then each chunk directly to `writer`, without
+    /// building the whole file in memory like `as_bytes`
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> super::Result<()> {
+        writer.write_all(&Self::STANDARD_HEADER)?;
+        for chunk in &self.chunks {
+            chunk.write_to(writer)?;
+        }
+        writer.write_all(&self.trailing)?;
+        Ok(())
+    }
+    /// The whole file, as `write_to` writes it
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size_summary().total);
+        self.write_to(&mut bytes)
+            .expect("writing to a Vec never fails");
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::raster::Raster;
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    fn testing_chunks() -> Vec<Chunk> {
+        vec![
+            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
+            chunk_from_strings("miDl", "I am another chunk").unwrap(),
+            chunk_from_strings("LASt", "I am the last chunk").unwrap(),
+        ]
+    }
+
+    fn testing_png() -> Png {
+        let chunks = testing_chunks();
+        Png::from_chunks(chunks)
+    }
+
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> crate::Result<Chunk> {
+        let chunk_type = ChunkType::from_str(chunk_type)?;
+        let data: Vec<u8> = data.bytes().collect();
+
+        Ok(Chunk::new(chunk_type, data))
+    }
+
+    #[test]
+    fn test_from_chunks() {
+        let chunks = testing_chunks();
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_valid_from_bytes() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert!(png.is_ok());
+    }
+
+    #[test]
+    fn test_from_owned_bytes() {
+        let png = Png::try_from(PNG_FILE.to_vec()).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = [13, 80, 78, 71, 13, 10, 26, 10]
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let mut chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        #[rustfmt::skip]
+        let mut bad_chunk = vec![
+            0, 0, 0, 5,         // length
+            32, 117, 83, 116,   // Chunk Type (bad)
+            65, 64, 65, 66, 67, // Data
+            1, 2, 3, 4, 5       // CRC (bad)
+        ];
+
+        chunk_bytes.append(&mut bad_chunk);
+
+        let png = Png::try_from(chunk_bytes.as_ref());
+
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_list_chunks() {
+        let png = testing_png();
+        let chunks = png.chunks();
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn test_chunk_by_type() {
+        let png = testing_png();
+        let chunk = png.chunk_by_type("FrSt").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "FrSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
+    }
+
+    #[test]
+    fn test_trailing_bytes_round_trip() {
+        let png = png_from_types(&["IHDR", "IDAT", "IEND"]);
+        let mut bytes = png.as_bytes();
+        let trailing = *b"hidden after end";
+        bytes.extend(trailing);
+        let parsed = Png::try_from(&bytes[..]).unwrap();
+        assert_eq!(parsed.chunks().len(), 3);
+        assert_eq!(parsed.trailing_bytes(), trailing);
+        assert_eq!(parsed.as_bytes(), bytes);
+        assert!(parsed
+            .to_string()
+            .ends_with("16 trailing bytes after IEND\n"));
+
+        let kept = Png::try_from_keeping_bad_crcs(&bytes).unwrap();
+        assert_eq!(kept.trailing_bytes(), trailing);
+        let (lenient, resyncs) = Png::try_from_lenient(&bytes).unwrap();
+        assert!(resyncs.is_empty());
+        assert_eq!(lenient.trailing_bytes(), trailing);
+        let streamed = Png::read_chunks(&bytes[..]).unwrap().count();
+        assert_eq!(streamed, 3);
+        assert!(Png::try_from(&png.as_bytes()[..])
+            .unwrap()
+            .trailing_bytes()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_stash_round_trip() {
+        let mut png = testing_png();
+        let data = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+        assert_eq!(png.stash("secret.zip", &data, 300).unwrap(), 5);
+        let png = Png::try_from(&png.as_bytes()[..]).unwrap();
+        assert_eq!(png.unstash().unwrap(), ("secret.zip".to_string(), data));
+
+        let mut png = png;
+        assert!(png.stash("again", b"", 300).is_err());
+        png.chunks.pop();
+        png.chunks.pop();
+        assert!(png.unstash().is_err());
+        assert!(testing_png().unstash().is_err());
+    }
+
+    #[test]
+    fn test_chunk_at() {
+        let png = testing_png();
+        assert_eq!(png.chunk_at(1).unwrap().chunk_type().to_string(), "miDl");
+        assert!(png.chunk_at(3).is_none());
+    }
+
+    #[test]
+    fn test_chunks_by_type() {
+        let mut png = testing_png();
+        for message in ["one", "two", "three"] {
+            png.append_chunk(chunk_from_strings("ruSt", message).unwrap());
+        }
+        let messages = png
+            .chunks_by_type("ruSt")
+            .iter()
+            .map(|chunk| chunk.data_as_string().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["one", "two", "three"]);
+        assert!(png.chunks_by_type("nOpE").is_empty());
+    }
+
+    #[test]
+    fn test_size_summary() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let summary = png.size_summary();
+        assert_eq!(summary.total, png.as_bytes().len());
+        assert_eq!(summary.chunks, png.chunks().len());
+        assert_eq!(summary.overhead, 8 + 12 * png.chunks().len());
+
+        png.append_chunk(chunk_from_strings("ruSt", "hello").unwrap());
+        png.trailing = b"extra".to_vec();
+        let grown = png.size_summary();
+        assert_eq!(grown.total, png.as_bytes().len());
+        assert_eq!(grown.ancillary_data, summary.ancillary_data + 5);
+        assert_eq!(grown.critical_data, summary.critical_data);
+        assert_eq!(grown.trailing, 5);
+    }
+
+    #[test]
+    fn test_find_chunk() {
+        let mut png = testing_png();
+        for message in ["v1:first", "v2:second", "v2:third"] {
+            png.append_chunk(chunk_from_strings("ruSt", message).unwrap());
+        }
+        let found = png
+            .find_chunk(|chunk| {
+                chunk.chunk_type().to_string() == "ruSt" && chunk.data().starts_with(b"v2:")
+            })
+            .unwrap();
+        assert_eq!(found.data_as_string().unwrap(), "v2:second");
+        assert!(png
+            .find_chunk(|chunk| chunk.data().starts_with(b"v3:"))
+            .is_none());
+        assert_eq!(
+            png.find_chunk(|chunk| chunk.length() == 8)
+                .unwrap()
+                .data_as_string()
+                .unwrap(),
+            "v1:first"
+        );
+    }
+
+    #[test]
+    fn test_chunks_matching_ignoring_case() {
+        let png = testing_png();
+        assert!(png.chunks_matching("frst", false).is_empty());
+        let chunks = png.chunks_matching("frst", true);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].chunk_type().to_string(), "FrSt");
+    }
+
+    #[test]
+    fn test_append_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        let chunk = png.chunk_by_type("TeSt").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "TeSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+    }
+
+    #[test]
+    fn test_append_chunk_keeps_iend_last() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("IEND", "").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        let iend = chunk_from_strings("IEND", "").unwrap().as_bytes();
+        assert!(png.as_bytes().ends_with(&iend));
+        let types = png
+            .chunks()
+            .iter()
+            .rev()
+            .take(2)
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["IEND", "TeSt"]);
+    }
+
+    #[test]
+    fn test_remove_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        png.remove_chunk("TeSt").unwrap();
+        let chunk = png.chunk_by_type("TeSt");
+        assert!(chunk.is_none());
+    }
+
+    #[test]
+    fn test_diagnose_valid_file() {
+        assert_eq!(Png::diagnose(&testing_png().as_bytes()), None);
+    }
+
+    #[test]
+    fn test_diagnose_truncated_crc() {
+        let bytes = testing_png().as_bytes();
+        let bytes = &bytes[..bytes.len() - 2];
+        let diagnostic = Png::diagnose(bytes).unwrap();
+        let crc_offset = bytes.len() - 2;
+        assert_eq!(
+            diagnostic.message,
+            format!("expected CRC at offset {crc_offset}, got EOF")
+        );
+        assert_eq!(diagnostic.offset, bytes.len());
+        assert!(diagnostic.context.ends_with("[EOF]"));
+    }
+
+    #[test]
+    fn test_diagnose_bad_chunk_type_and_crc() {
+        let mut bytes = testing_png().as_bytes();
+        bytes[12] = b'1';
+        let diagnostic = Png::diagnose(&bytes).unwrap();
+        assert_eq!(diagnostic.offset, 12);
+        assert!(diagnostic.message.starts_with("expected chunk type"));
+        assert!(diagnostic.context.contains("[31]"));
+
+        let mut bytes = testing_png().as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        let diagnostic = Png::diagnose(&bytes).unwrap();
+        assert_eq!(diagnostic.offset, last - 3);
+        assert!(diagnostic.message.starts_with("expected CRC 0x"));
+    }
+
+    #[test]
+    fn test_set_gamma() {
+        let mut png = Png::from_chunks(
+            ["IHDR", "IDAT", "IEND"]
+                .iter()
+                .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+                .collect(),
+        );
+        png.set_gamma(Gamma(45455)).unwrap();
+        assert_eq!(&png.chunks()[1].chunk_type().to_string(), "gAMA");
+        png.set_gamma(Gamma(100000)).unwrap();
+        assert_eq!(png.chunks().len(), 4);
+        let gamma = Gamma::try_from(png.chunk_by_type("gAMA").unwrap().data()).unwrap();
+        assert_eq!(gamma.value(), 1.0);
+    }
+
+    #[test]
+    fn test_alternate_display_explains_chunks() {
+        let png = testing_png();
+        assert!(!png.to_string().contains("about:"));
+        let explained = format!("{png:#}");
+        assert_eq!(explained.matches("about: custom/private chunk").count(), 3);
+    }
+
+    #[test]
+    fn test_display_with_registered_interpreter() {
+        let png = testing_png();
+        assert!(!png.to_string().contains("decoded:"));
+        let interpreters =
+            Interpreters::new().register(*b"miDl", |data| format!("{} bytes", data.len()));
+        let shown = png.display_with(&interpreters).to_string();
+        assert_eq!(shown.matches("decoded:").count(), 1);
+        assert!(shown.contains("    decoded: 18 bytes"));
+    }
+
+    #[test]
+    fn test_byte_range_of_chunk() {
+        let png = testing_png();
+        let bytes = png.as_bytes();
+        for (index, chunk) in png.chunks().iter().enumerate() {
+            let (start, end) = png.byte_range_of_chunk(index).unwrap();
+            assert_eq!(&bytes[start..end], &chunk.as_bytes()[..]);
+        }
+        assert_eq!(png.byte_range_of_chunk(2).unwrap().1, bytes.len());
+        assert_eq!(png.byte_range_of_chunk(3), None);
+    }
+
+    #[test]
+    fn test_chunk_meta_display() {
+        let meta = &testing_png().chunk_map()[1];
+        assert_eq!(
+            meta.to_string(),
+            format!(
+                "   1  miDl  ancillary  private  safe-to-copy    offset {:>8}  length {:>8}  crc {:08x}",
+                meta.offset, meta.length, meta.crc
+            )
+        );
+    }
+
+    #[test]
+    fn test_try_from_bytes_strict_and_lenient() {
+        let png = testing_png();
+        let mut bytes = png.as_bytes();
+        // The first byte of the first chunk's data
+        bytes[16] ^= 1;
+        assert!(matches!(
+            Png::try_from_bytes(&bytes, true),
+            Err(PngError::CrcMismatch { .. })
+        ));
+        let lenient = Png::try_from_bytes(&bytes, false).unwrap();
+        let chunk = &lenient.chunks()[0];
+        assert!(!chunk.is_crc_valid());
+        assert_eq!(chunk.crc(), png.chunks()[0].crc());
+        assert_ne!(chunk.computed_crc(), chunk.crc());
+        assert!(lenient.chunks()[1..].iter().all(Chunk::is_crc_valid));
+        assert!(Png::try_from_bytes(&png.as_bytes(), true).is_ok());
+    }
+
+    #[test]
+    fn test_write_to_matches_as_bytes() {
+        let png = testing_png();
+        let mut written = Vec::new();
+        png.write_to(&mut written).unwrap();
+        assert_eq!(written, png.as_bytes());
+    }
+
+    #[test]
+    fn test_from_chunk_stream() {
+        let png = testing_png();
+        let stream = &png.as_bytes()[8..];
+        assert!(matches!(
+            Png::try_from(stream),
+            Err(PngError::InvalidSignature)
+        ));
+        let parsed = Png::from_chunk_stream(stream).unwrap();
+        assert_eq!(parsed.as_bytes(), png.as_bytes());
+        assert!(Png::from_chunk_stream(&png.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let err = Png::try_from(&[][..]).err().unwrap();
+        assert_eq!(err.to_string(), "file is empty (0 bytes), not a PNG");
+        assert!(Png::try_from_lenient(&[]).is_err());
+    }
+
+    #[test]
+    fn test_signature() {
+        let png = Png::try_from(&Png::STANDARD_HEADER[..]).unwrap();
+        assert!(png.chunks().is_empty());
+        let jpeg = [0xff, 0xd8, 0xff, 0xe0, 0, 16, b'J', b'F', b'I', b'F', 0];
+        for bytes in [&jpeg[..], &Png::STANDARD_HEADER[..5]] {
+            let err = Png::try_from(bytes).err().unwrap();
+            assert!(matches!(err, PngError::InvalidSignature));
+            assert_eq!(err.to_string(), "not a PNG file: invalid signature");
+            assert!(Png::try_from_lenient(bytes).is_err());
+            assert!(Png::try_from_keeping_bad_crcs(bytes).is_err());
+        }
+    }
+
+    #[test]
+    fn test_default_png() {
+        let mut png = Png::default();
+        assert_eq!(png.as_bytes(), Png::STANDARD_HEADER);
+        assert!(Png::try_from(&png.as_bytes()[..])
+            .unwrap()
+            .chunks()
+            .is_empty());
+        let ihdr = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+        png.append_chunk_checked(Chunk::new(
+            ChunkType::from_str("IHDR").unwrap(),
+            ihdr.to_vec(),
+        ))
+        .unwrap();
+        png.append_chunk_checked(chunk_from_strings("IDAT", "").unwrap())
+            .unwrap();
+        png.append_chunk_checked(chunk_from_strings("IEND", "").unwrap())
+            .unwrap();
+        let parsed = Png::try_from(&png.as_bytes()[..]).unwrap();
+        assert_eq!(parsed.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_rename_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("miDl", "again").unwrap());
+        let chunk_type = |s: &str| ChunkType::from_str(s).unwrap();
+        let counts = png.rename_chunks(&[
+            (chunk_type("miDl"), chunk_type("LASt")),
+            (chunk_type("LASt"), chunk_type("miDl")),
+            (chunk_type("noNe"), chunk_type("soMe")),
+        ]);
+        assert_eq!(counts, [2, 1, 0]);
+        let types = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["FrSt", "LASt", "miDl", "LASt"]);
+        assert!(png.chunks().iter().all(Chunk::is_crc_valid));
+    }
+
+    #[test]
+    fn test_private_chunks() {
+        let mut png = Png::from_chunks(
+            ["IHDR", "tEXt", "ruSt", "IDAT", "prVt", "IEND"]
+                .iter()
+                .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+                .collect(),
+        );
+        let private = |png: &Png| {
+            png.private_chunks()
+                .into_iter()
+                .map(|(index, chunk)| (index, chunk.chunk_type().to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            private(&png),
+            [(2, "ruSt".to_string()), (4, "prVt".to_string())]
+        );
+        png.remove_chunks_where(|chunk| !chunk.chunk_type().is_public());
+        assert!(private(&png).is_empty());
+    }
+
+    #[test]
+    fn test_tail() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("IEND", "").unwrap());
+        let types = |png: &Png| {
+            png.chunks()
+                .iter()
+                .map(|chunk| chunk.chunk_type().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(types(&png.tail(2)), ["LASt", "IEND"]);
+        assert_eq!(types(&png.tail(0)), ["IEND"]);
+        png.insert_chunk(png.chunks().len(), chunk_from_strings("ruSt", "").unwrap())
+            .unwrap();
+        assert_eq!(types(&png.tail(1)), ["ruSt", "IEND"]);
+        assert_eq!(png.tail(100).chunks().len(), 5);
     }
-}
 
-impl Display for Png {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Header: {:?}", self.header())?;
-        for chunk in &self.chunks {
-            write!(f, "{chunk}")?;
+    #[test]
+    fn test_chunks_mut() {
+        let mut png = testing_png();
+        for chunk in png.chunks_mut() {
+            let mut data = chunk.data().to_vec();
+            data.extend(b"!");
+            chunk.set_data(data);
         }
-        Ok(())
+        let png = Png::try_from(&png.as_bytes()[..]).unwrap();
+        assert_eq!(
+            png.chunks()[0].data_as_string().unwrap(),
+            "I am the first chunk!"
+        );
+        assert!(png
+            .chunks()
+            .iter()
+            .all(|chunk| chunk.data().ends_with(b"!")));
     }
-}
 
-impl Png {
-    pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+    #[test]
+    fn test_all_text() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("tEXt", "Title\0Dice").unwrap());
+        png.append_chunk(chunk_from_strings("iTXt", "Author\0\0\0en\0\0Me").unwrap());
+        let keywords = png
+            .all_text()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.keyword, entry.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keywords,
+            [
+                ("Title".to_string(), "Dice".to_string()),
+                ("Author".to_string(), "Me".to_string())
+            ]
+        );
+    }
 
-    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
-        Self { chunks }
+    #[test]
+    fn test_keeping_bad_crcs() {
+        let bytes = testing_png().as_bytes();
+        let png = Png::try_from_keeping_bad_crcs(&bytes).unwrap();
+        assert!(png.chunks().iter().all(Chunk::is_crc_valid));
+
+        let mut bytes = bytes;
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        let png = Png::try_from_keeping_bad_crcs(&bytes).unwrap();
+        let valid = png
+            .chunks()
+            .iter()
+            .map(Chunk::is_crc_valid)
+            .collect::<Vec<_>>();
+        assert_eq!(valid, [true, true, false]);
+        assert_eq!(png.as_bytes(), bytes);
+        assert!(Png::try_from_keeping_bad_crcs(&bytes[..bytes.len() - 1]).is_err());
     }
-    pub fn append_chunk(&mut self, chunk: Chunk) {
-        self.chunks.push(chunk)
+
+    #[test]
+    fn test_validate() {
+        assert!(png_from_types(&["IHDR", "IDAT", "IEND"])
+            .validate()
+            .is_empty());
+        assert_eq!(
+            png_from_types(&["IHDR", "IDAT"]).validate(),
+            [ValidationError::IendNotLast {
+                found: Some("IDAT".to_string())
+            }]
+        );
+        assert_eq!(
+            png_from_types(&["IHDR", "IDAT", "IEND", "tEXt", "IDAT"]).validate(),
+            [
+                ValidationError::IendNotLast {
+                    found: Some("IDAT".to_string())
+                },
+                ValidationError::CriticalAfterIend {
+                    index: 4,
+                    chunk_type: "IDAT".to_string()
+                },
+                ValidationError::Structure(
+                    "IDAT chunks are not contiguous: IEND at index 2 interrupts them".to_string()
+                ),
+            ]
+        );
+
+        let mut png = png_from_types(&["ihdr", "IDAT", "IEND"]);
+        png.chunks_mut()[2].set_data(b"x".to_vec());
+        let mut bytes = png.as_bytes();
+        let crc_offset = 8 + 12 + 12 - 1;
+        bytes[crc_offset] ^= 1;
+        let png = Png::try_from_keeping_bad_crcs(&bytes).unwrap();
+        let errors = png.validate();
+        assert_eq!(
+            errors[..2],
+            [
+                ValidationError::BadCrc {
+                    index: 1,
+                    chunk_type: "IDAT".to_string()
+                },
+                ValidationError::IendNotEmpty { length: 1 },
+            ]
+        );
+        assert_eq!(errors.len(), 3);
+        assert!(errors[2].to_string().contains("casing is significant"));
+        assert!(Png::default().validate().len() > 1);
+
+        let mut bytes = png_from_types(&["IHDR", "IDAT", "IEND"]).as_bytes();
+        let after = png_from_types(&["IDAT", "IEND"]).as_bytes();
+        bytes.extend(&after[8..]);
+        let png = Png::try_from(&bytes[..]).unwrap();
+        assert!(png
+            .validate()
+            .contains(&ValidationError::CriticalAfterIend {
+                index: 3,
+                chunk_type: "IDAT".to_string()
+            }));
+        bytes.truncate(bytes.len() - after.len() + 8);
+        bytes.extend(b"junk");
+        let png = Png::try_from(&bytes[..]).unwrap();
+        assert_eq!(
+            png.validate(),
+            [ValidationError::TrailingBytes { length: 4 }]
+        );
     }
-    pub fn remove_chunk(&mut self, chunk_type: &str) -> super::Result<Chunk> {
-        let mut index = 0;
-        while index < self.chunks.len() {
-            let chunk = &self.chunks[index];
-            if chunk.chunk_type().to_string() == chunk_type {
-                let chunk = chunk.clone();
-                self.chunks.remove(index);
-                return Ok(chunk);
-            }
-            index += 1;
-        }
-        Err("Chunk not found".into())
+
+    #[test]
+    fn test_validate_progress_counts_chunks_after_iend() {
+        let mut bytes = png_from_types(&["IHDR", "IDAT", "IEND"]).as_bytes();
+        bytes.extend(&png_from_types(&["IDAT", "IEND"]).as_bytes()[8..]);
+        let png = Png::try_from(&bytes[..]).unwrap();
+        let mut calls = Vec::new();
+        png.validate_with_progress(|checked, total| calls.push((checked, total)));
+        assert_eq!(calls, [(1, 5), (2, 5), (3, 5), (4, 5), (5, 5)]);
     }
-    pub fn header(&self) -> &[u8; 8] {
-        &Self::STANDARD_HEADER
+
+    #[test]
+    fn test_validate_unknown_critical() {
+        let errors = png_from_types(&["IHDR", "ZZZZ", "IDAT", "IEND"]).validate();
+        assert_eq!(
+            errors,
+            [ValidationError::UnknownCritical {
+                index: 1,
+                chunk_type: "ZZZZ".to_string()
+            }]
+        );
+        assert!(errors[0].is_warning());
+        assert_eq!(
+            errors[0].to_string(),
+            "unknown critical chunk ZZZZ at index 1"
+        );
+        assert!(png_from_types(&["IHDR", "PLTE", "IDAT", "zzzz", "IEND"])
+            .validate()
+            .is_empty());
     }
-    pub fn chunks(&self) -> &[Chunk] {
-        &self.chunks
+
+    #[test]
+    fn test_validate_ihdr_first() {
+        assert!(png_from_types(&["IHDR", "IDAT", "IEND"])
+            .validate_ihdr_first()
+            .is_ok());
+        let err = png_from_types(&["ihdr", "IDAT", "IEND"])
+            .validate_ihdr_first()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected IHDR as the first chunk, found 'ihdr' — note PNG chunk type casing is significant"
+        );
+        let err = testing_png().validate_ihdr_first().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected IHDR as the first chunk, found 'FrSt'"
+        );
+        assert!(Png::default().validate_ihdr_first().is_err());
     }
-    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
-        self.chunks
+
+    #[test]
+    fn test_recompute_crcs() {
+        let mut bytes = testing_png().as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        let mut png = Png::try_from_keeping_bad_crcs(&bytes).unwrap();
+        assert_eq!(png.recompute_crcs(), 1);
+        assert!(png.chunks().iter().all(Chunk::is_crc_valid));
+        assert_eq!(png.as_bytes(), testing_png().as_bytes());
+        assert_eq!(png.recompute_crcs(), 0);
+    }
+
+    #[test]
+    fn test_diagnose_bad_header() {
+        assert!(Png::diagnose(&[137, 80]).unwrap().message.contains("EOF"));
+        assert_eq!(Png::diagnose(&[0; 16]).unwrap().offset, 0);
+    }
+
+    /// Empty chunks of the given types, except for a valid 1x1 RGBA IHDR
+    fn png_from_types(chunk_types: &[&str]) -> Png {
+        let ihdr = [0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0];
+        Png::from_chunks(
+            chunk_types
+                .iter()
+                .map(|&chunk_type| match chunk_type {
+                    "IHDR" => Chunk::new(ChunkType::from_str("IHDR").unwrap(), ihdr.to_vec()),
+                    _ => chunk_from_strings(chunk_type, "").unwrap(),
+                })
+                .collect(),
+        )
+    }
+
+    fn chunk_types(png: &Png) -> Vec<String> {
+        png.chunks()
             .iter()
-            .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect()
     }
-    pub fn as_bytes(&self) -> Vec<u8> {
-        Self::STANDARD_HEADER
-            .into_iter()
-            .chain(self.chunks.iter().flat_map(|chunk| chunk.as_bytes()))
-            .collect::<Vec<u8>>()
+
+    #[test]
+    fn test_chunk_type_counts() {
+        let png = png_from_types(&["IHDR", "tEXt", "IDAT", "IDAT", "IDAT", "tEXt", "IEND"]);
+        let counts = png.chunk_type_counts();
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts["IDAT"], 3);
+        assert_eq!(counts["tEXt"], 2);
+        assert_eq!(counts["IHDR"], 1);
+        assert_eq!(counts.get("PLTE"), None);
+        assert!(Png::default().chunk_type_counts().is_empty());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::chunk::Chunk;
-    use crate::chunk_type::ChunkType;
-    use std::convert::TryFrom;
-    use std::str::FromStr;
+    #[test]
+    fn test_read_chunks_matches_try_from() {
+        let bytes = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("ruSt", "one").unwrap(),
+            chunk_from_strings("IDAT", "pixels").unwrap(),
+            chunk_from_strings("ruSt", "two").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ])
+        .as_bytes();
+        let in_memory = Png::try_from(&bytes[..]).unwrap();
+        let streamed = Png::read_chunks(std::io::Cursor::new(&bytes))
+            .unwrap()
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(streamed.len(), in_memory.chunks().len());
+        let count = |chunks: &mut dyn Iterator<Item = &Chunk>| {
+            chunks
+                .filter(|chunk| chunk.chunk_type().to_string() == "ruSt")
+                .count()
+        };
+        assert_eq!(
+            count(&mut streamed.iter()),
+            count(&mut in_memory.chunks().iter())
+        );
+        assert_eq!(streamed[3].as_bytes(), in_memory.chunks()[3].as_bytes());
+    }
 
-    fn testing_chunks() -> Vec<Chunk> {
-        vec![
-            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
-            chunk_from_strings("miDl", "I am another chunk").unwrap(),
-            chunk_from_strings("LASt", "I am the last chunk").unwrap(),
-        ]
+    #[test]
+    fn test_read_chunks_errors() {
+        assert!(Png::read_chunks(std::io::Cursor::new(b"")).is_err());
+        assert!(matches!(
+            Png::read_chunks(std::io::Cursor::new(b"not a png")),
+            Err(PngError::InvalidSignature)
+        ));
+        let mut bytes = testing_png().as_bytes();
+        bytes.pop();
+        let mut reader = Png::read_chunks(std::io::Cursor::new(&bytes)).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        let Some(Err(error)) = reader.next() else {
+            panic!("expected an error for the truncated chunk");
+        };
+        assert!(error.to_string().contains("Truncated chunk"));
+        assert!(reader.next().is_none());
+
+        let mut bytes = testing_png().as_bytes();
+        bytes[16] ^= 1;
+        let mut reader = Png::read_chunks(std::io::Cursor::new(&bytes)).unwrap();
+        assert!(matches!(
+            reader.next(),
+            Some(Err(PngError::CrcMismatch { .. }))
+        ));
+        assert!(reader.next().is_none());
     }
 
-    fn testing_png() -> Png {
-        let chunks = testing_chunks();
-        Png::from_chunks(chunks)
+    #[test]
+    fn test_replace_chunk_data() {
+        let mut png = testing_png();
+        png.replace_chunk_data("miDl", b"a longer replacement".to_vec())
+            .unwrap();
+        assert_eq!(chunk_types(&png), ["FrSt", "miDl", "LASt"]);
+        let chunk = &png.chunks()[1];
+        assert_eq!(chunk.data(), b"a longer replacement");
+        assert_eq!(chunk.length(), 20);
+        assert!(chunk.is_crc_valid());
+        assert!(matches!(
+            png.replace_chunk_data("ruSt", Vec::new()),
+            Err(PngError::ChunkNotFound(_))
+        ));
     }
 
-    fn chunk_from_strings(chunk_type: &str, data: &str) -> crate::Result<Chunk> {
-        use std::str::FromStr;
+    #[test]
+    fn test_try_append_chunk() {
+        let mut png = png_from_types(&["IHDR", "IDAT", "IEND"]);
+        let error = png
+            .try_append_chunk(chunk_from_strings("IEND", "").unwrap())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The file already has an IEND chunk, and only one is allowed"
+        );
+        for _ in 0..2 {
+            png.try_append_chunk(chunk_from_strings("ruSt", "hi").unwrap())
+                .unwrap();
+        }
+        png.try_append_chunk(chunk_from_strings("IDAT", "").unwrap())
+            .unwrap();
+        assert_eq!(
+            chunk_types(&png),
+            ["IHDR", "IDAT", "ruSt", "ruSt", "IDAT", "IEND"]
+        );
+        assert!(png
+            .try_append_chunk(chunk_from_strings("IHDR", "").unwrap())
+            .is_err());
+    }
 
-        let chunk_type = ChunkType::from_str(chunk_type)?;
-        let data: Vec<u8> = data.bytes().collect();
+    #[test]
+    fn test_retain_critical() {
+        let mut png = png_from_types(&["IHDR", "sRGB", "gAMA", "IDAT", "tEXt", "RuSt", "IEND"]);
+        assert_eq!(png.retain_critical(), 3);
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT", "RuSt", "IEND"]);
+        assert_eq!(png.retain_critical(), 0);
+    }
 
-        Ok(Chunk::new(chunk_type, data))
+    #[test]
+    fn test_png_equality() {
+        let png = testing_png();
+        assert!(png == Png::try_from(&png.as_bytes()[..]).unwrap());
+        let mut reordered = testing_png();
+        let last = reordered.chunks.pop().unwrap();
+        reordered.chunks.insert(0, last);
+        assert!(png != reordered);
+        let mut changed = testing_png();
+        changed.chunks[0].set_data(b"other".to_vec());
+        assert!(png != changed);
     }
 
     #[test]
-    fn test_from_chunks() {
-        let chunks = testing_chunks();
-        let png = Png::from_chunks(chunks);
+    fn test_remove_duplicate_ancillary() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("tEXt", "a").unwrap(),
+            chunk_from_strings("IDAT", "pixels").unwrap(),
+            chunk_from_strings("IDAT", "pixels").unwrap(),
+            chunk_from_strings("tEXt", "b").unwrap(),
+            chunk_from_strings("tEXt", "a").unwrap(),
+            chunk_from_strings("ruSt", "a").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        assert_eq!(png.remove_duplicate_ancillary(), 1);
+        assert_eq!(
+            chunk_types(&png),
+            ["IHDR", "tEXt", "IDAT", "IDAT", "tEXt", "ruSt", "IEND"]
+        );
+        assert_eq!(png.chunks()[4].data(), b"b");
+        assert_eq!(png.remove_duplicate_ancillary(), 0);
+    }
+
+    #[test]
+    fn test_remove_empty_chunks() {
+        let mut png = png_from_types(&["IHDR", "IDAT", "IEND"]);
+        png.chunks_mut()[0].set_data(b"header".to_vec());
+        png.chunks_mut()[1].set_data(b"pixels".to_vec());
+        png.insert_chunk(1, chunk_from_strings("tEXt", "").unwrap())
+            .unwrap();
+        assert_eq!(png.remove_empty_chunks(), 1);
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn test_merge_idat() {
+        let ihdr = [0, 0, 0, 2, 0, 0, 0, 2, 8, 0, 0, 0, 0];
+        let idat = crate::zlib::compress(&[0, 10, 20, 0, 30, 40]);
+        let chunk = |chunk_type: &str, data: &[u8]| {
+            Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+        };
+        let original = Png::from_chunks(vec![
+            chunk("IHDR", &ihdr),
+            chunk("IDAT", &idat[..3]),
+            chunk("IDAT", &idat[3..7]),
+            chunk("IDAT", &idat[7..]),
+            chunk("IEND", &[]),
+        ]);
+        let mut png = Png::try_from(&original.as_bytes()[..]).unwrap();
+        assert_eq!(png.merge_idat(), 3);
+        let types = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+        let merged = png.chunk_by_type("IDAT").unwrap();
+        assert_eq!(merged.data(), idat);
+        assert!(merged.is_crc_valid());
+        let before = Raster::try_from(&original).unwrap();
+        let after = Raster::try_from(&png).unwrap();
+        assert_eq!(before.first_difference(&after).unwrap(), None);
+        assert_eq!(png.merge_idat(), 1);
+        assert_eq!(testing_png().merge_idat(), 0);
+    }
+
+    #[test]
+    fn test_split_idat() {
+        let ihdr = [0, 0, 0, 2, 0, 0, 0, 2, 8, 0, 0, 0, 0];
+        let idat = crate::zlib::compress(&[0, 10, 20, 0, 30, 40]);
+        let mut png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), ihdr.to_vec()),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), idat.clone()),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        let expected_count = idat.len().div_ceil(4);
+        assert_eq!(png.split_idat(4).unwrap(), expected_count);
+        let idats = png.chunks_by_type("IDAT");
+        assert_eq!(idats.len(), expected_count);
+        assert!(idats.iter().all(|chunk| chunk.length() <= 4));
+        assert_eq!(
+            png.chunks().last().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+        assert!(png.validate_idat_contiguous().is_ok());
+        assert_eq!(png.merge_idat(), expected_count);
+        assert_eq!(png.chunk_by_type("IDAT").unwrap().data(), idat);
+        assert!(png.split_idat(0).is_err());
+    }
+
+    #[test]
+    fn test_try_insert_chunk() {
+        let mut png = png_from_types(&["IHDR", "IDAT", "IEND"]);
+        let chunk = |chunk_type: &str| chunk_from_strings(chunk_type, "").unwrap();
+        let error = png.try_insert_chunk(1, chunk("IEND")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The file already has an IEND chunk, and only one is allowed"
+        );
+        assert!(png.try_insert_chunk(1, chunk("IHDR")).is_err());
+        assert!(png.try_insert_chunk(0, chunk("ruSt")).is_err());
+        assert!(png.try_insert_chunk(3, chunk("ruSt")).is_err());
+        assert!(png
+            .try_insert_chunk(9, chunk("ruSt"))
+            .unwrap_err()
+            .to_string()
+            .contains("out of range"));
+        png.try_insert_chunk(2, chunk("ruSt")).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "IDAT", "ruSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_insert_chunk() {
+        let mut png = png_from_types(&["IHDR", "IDAT", "IEND"]);
+        png.insert_chunk(0, chunk_from_strings("frSt", "").unwrap())
+            .unwrap();
+        png.insert_chunk(2, chunk_from_strings("miDl", "").unwrap())
+            .unwrap();
+        let types = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["frSt", "IHDR", "miDl", "IDAT", "IEND"]);
+        assert!(png
+            .insert_chunk(6, chunk_from_strings("laSt", "").unwrap())
+            .is_err());
+        assert_eq!(png.chunks().len(), 5);
+    }
+
+    #[test]
+    fn test_ancillary_insert_positions() {
+        let png = png_from_types(&["IHDR", "gAMA", "IDAT", "IDAT", "IDAT", "tEXt", "IEND"]);
+        assert_eq!(png.ancillary_insert_positions(), [1, 2, 5, 6]);
+        for index in png.ancillary_insert_positions() {
+            let mut png = png_from_types(&["IHDR", "gAMA", "IDAT", "IDAT", "IDAT", "tEXt", "IEND"]);
+            png.insert_chunk(index, chunk_from_strings("ruSt", "hi").unwrap())
+                .unwrap();
+            let mut rebuilt = Png::default();
+            for chunk in png.chunks() {
+                rebuilt.append_chunk_checked(chunk.clone()).unwrap();
+            }
+            assert!(png.validate_idat_contiguous().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_append_chunk_checked() {
+        let mut png = Png::from_chunks(Vec::new());
+        for chunk_type in [
+            "IHDR", "gAMA", "PLTE", "tRNS", "IDAT", "IDAT", "tEXt", "IEND",
+        ] {
+            png.append_chunk_checked(chunk_from_strings(chunk_type, "").unwrap())
+                .unwrap();
+        }
+        assert_eq!(png.chunks().len(), 8);
+    }
+
+    #[test]
+    fn test_append_chunk_checked_rejects_misplaced() {
+        let append = |chunk_types: &[&str], chunk_type: &str| {
+            let chunks = chunk_types
+                .iter()
+                .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+                .collect();
+            Png::from_chunks(chunks)
+                .append_chunk_checked(chunk_from_strings(chunk_type, "").unwrap())
+        };
+        assert!(append(&[], "tEXt").is_err());
+        assert!(append(&["IHDR"], "IHDR").is_err());
+        assert!(append(&["IHDR", "IDAT", "IEND"], "tEXt").is_err());
+        assert!(append(&["IHDR", "IDAT"], "PLTE").is_err());
+        assert!(append(&["IHDR", "PLTE"], "PLTE").is_err());
+        assert!(append(&["IHDR", "PLTE"], "gAMA").is_err());
+        assert!(append(&["IHDR", "IDAT", "tEXt"], "IDAT").is_err());
+        assert!(append(&["IHDR"], "IEND").is_err());
+    }
+
+    #[test]
+    fn test_remove_chunk_returns_removed_chunk() {
+        let mut png = testing_png();
+        let expected = chunk_from_strings("TeSt", "Message").unwrap();
+        png.append_chunk(expected.clone());
+        let removed = png.remove_chunk("TeSt").unwrap();
+        assert_eq!(removed.as_bytes(), expected.as_bytes());
+        assert!(matches!(
+            png.remove_chunk("TeSt"),
+            Err(PngError::ChunkNotFound(chunk_type)) if chunk_type == "TeSt"
+        ));
+    }
 
+    #[test]
+    fn test_remove_all_chunks() {
+        let mut png = testing_png();
+        assert_eq!(png.remove_all_chunks("TeSt"), 0);
+        assert_eq!(png.chunks().len(), 3);
+        png.append_chunk(chunk_from_strings("TeSt", "one").unwrap());
+        assert_eq!(png.remove_all_chunks("TeSt"), 1);
+        for message in ["one", "two", "three"] {
+            png.append_chunk(chunk_from_strings("TeSt", message).unwrap());
+        }
+        assert_eq!(png.remove_all_chunks("TeSt"), 3);
+        assert!(png.chunk_by_type("TeSt").is_none());
         assert_eq!(png.chunks().len(), 3);
     }
 
     #[test]
-    fn test_valid_from_bytes() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
-            .into_iter()
-            .flat_map(|chunk| chunk.as_bytes())
-            .collect();
+    fn test_remove_chunk_at() {
+        let mut png = testing_png();
+        let chunk = png.remove_chunk_at(1).unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "miDl");
+        assert_eq!(png.chunks().len(), 2);
+        assert!(png.remove_chunk_at(2).is_err());
+    }
 
-        let bytes: Vec<u8> = Png::STANDARD_HEADER
+    #[test]
+    fn test_lenient_resyncs_past_garbage() {
+        let chunks = testing_chunks();
+        let mut bytes: Vec<u8> = Png::STANDARD_HEADER
             .iter()
-            .chain(chunk_bytes.iter())
             .copied()
+            .chain(chunks[0].as_bytes())
             .collect();
+        let garbage_offset = bytes.len();
+        bytes.extend([0xff, 0x00, 0x13, 0x37, 0x01]);
+        let resync_offset = bytes.len();
+        bytes.extend(chunks[1].as_bytes());
+        bytes.extend(chunks[2].as_bytes());
 
-        let png = Png::try_from(bytes.as_ref());
+        let (png, resyncs) = Png::try_from_lenient(bytes.as_ref()).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(
+            resyncs,
+            vec![Resync {
+                from: garbage_offset,
+                to: resync_offset
+            }]
+        );
+    }
 
-        assert!(png.is_ok());
+    #[test]
+    fn test_lenient_on_valid_png() {
+        let (png, resyncs) = Png::try_from_lenient(&PNG_FILE[..]).unwrap();
+        assert!(resyncs.is_empty());
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
     }
 
     #[test]
-    fn test_invalid_header() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
-            .into_iter()
-            .flat_map(|chunk| chunk.as_bytes())
-            .collect();
+    fn test_chunk_map() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let map = png.chunk_map();
+        let bytes = png.as_bytes();
 
-        let bytes: Vec<u8> = [13, 80, 78, 71, 13, 10, 26, 10]
+        assert_eq!(map.len(), png.chunks().len());
+        assert_eq!(map[0].chunk_type, "IHDR");
+        assert_eq!(map[0].offset, 8);
+        assert!(map[0].is_critical);
+        assert!(!map[1].is_critical);
+        for (meta, chunk) in map.iter().zip(png.chunks()) {
+            let end = meta.offset + chunk.serialized_len();
+            assert_eq!(&bytes[meta.offset..end], &chunk.as_bytes()[..]);
+            assert!(meta.is_valid_crc);
+        }
+    }
+
+    #[test]
+    fn test_seal_and_verify() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.verify_seal().is_err());
+
+        png.seal();
+        let png = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert!(png.verify_seal().unwrap());
+    }
+
+    #[test]
+    fn test_seal_detects_tampering() {
+        let mut png = testing_png();
+        png.seal();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        assert!(!png.verify_seal().unwrap());
+
+        png.seal();
+        assert!(png.verify_seal().unwrap());
+        let seals = png
+            .chunks()
             .iter()
-            .chain(chunk_bytes.iter())
-            .copied()
-            .collect();
+            .filter(|chunk| chunk.chunk_type().to_string() == Png::SEAL_CHUNK_TYPE)
+            .count();
+        assert_eq!(seals, 1);
+    }
 
-        let png = Png::try_from(bytes.as_ref());
+    #[test]
+    fn test_ihdr() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let ihdr = png.ihdr().unwrap();
+        assert_eq!((ihdr.width, ihdr.height), (50, 50));
+        assert_eq!(ihdr.interlace, crate::ihdr::Interlace::None);
+        assert!(testing_png().ihdr().is_none());
+    }
 
-        assert!(png.is_err());
+    #[test]
+    fn test_metadata_from_seekable_skips_idat() {
+        let full = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut reader = std::io::Cursor::new(&PNG_FILE[..]);
+        let png = Png::metadata_from_seekable(&mut reader).unwrap();
+
+        assert_eq!(png.chunks().len(), full.chunks().len());
+        for (skimmed, chunk) in png.chunks().iter().zip(full.chunks()) {
+            assert_eq!(skimmed.chunk_type(), chunk.chunk_type());
+            assert_eq!(skimmed.length(), chunk.length());
+            assert_eq!(skimmed.crc(), chunk.crc());
+        }
+        let idat = png.chunk_by_type("IDAT").unwrap();
+        assert!(idat.is_data_skipped());
+        assert!(idat.data().is_empty());
+        assert!(!png.chunk_by_type("IHDR").unwrap().is_data_skipped());
     }
 
     #[test]
-    fn test_invalid_chunk() {
-        let mut chunk_bytes: Vec<u8> = testing_chunks()
-            .into_iter()
-            .flat_map(|chunk| chunk.as_bytes())
-            .collect();
+    fn test_offsets_of_skipped_chunks() {
+        let full = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut reader = std::io::Cursor::new(&PNG_FILE[..]);
+        let png = Png::metadata_from_seekable(&mut reader).unwrap();
 
-        #[rustfmt::skip]
-        let mut bad_chunk = vec![
-            0, 0, 0, 5,         // length
-            32, 117, 83, 116,   // Chunk Type (bad)
-            65, 64, 65, 66, 67, // Data
-            1, 2, 3, 4, 5       // CRC (bad)
-        ];
+        let offsets = |png: &Png| {
+            png.chunk_map()
+                .iter()
+                .map(|meta| meta.offset)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(offsets(&png), offsets(&full));
+        let last = png.chunks().len() - 1;
+        assert_eq!(png.byte_range_of_chunk(last).unwrap().1, PNG_FILE.len());
+        for index in 0..=last {
+            assert_eq!(
+                png.byte_range_of_chunk(index),
+                full.byte_range_of_chunk(index)
+            );
+        }
+    }
 
-        chunk_bytes.append(&mut bad_chunk);
+    #[test]
+    fn test_stream_chunk_data() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut reader = std::io::Cursor::new(&PNG_FILE[..]);
+        let mut out = Vec::new();
+        let written = Png::stream_chunk_data(&mut reader, "IDAT", &mut out).unwrap();
 
-        let png = Png::try_from(chunk_bytes.as_ref());
+        assert_eq!(written, out.len() as u64);
+        assert_eq!(out, png.chunk_by_type("IDAT").unwrap().data());
+    }
 
-        assert!(png.is_err());
+    #[test]
+    fn test_stream_chunk_data_where() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut reader = std::io::Cursor::new(&PNG_FILE[..]);
+        let mut out = Vec::new();
+        let mut seen = 0;
+        let second = |_: &ChunkType| {
+            seen += 1;
+            seen == 2
+        };
+        let written = Png::stream_chunk_data_where(&mut reader, second, &mut out).unwrap();
+
+        assert_eq!(written, Some(out.len() as u64));
+        assert_eq!(out, png.chunks()[1].data());
+        let mut reader = std::io::Cursor::new(&PNG_FILE[..]);
+        let none = Png::stream_chunk_data_where(&mut reader, |_| false, &mut out).unwrap();
+        assert_eq!(none, None);
     }
 
     #[test]
-    fn test_list_chunks() {
-        let png = testing_png();
-        let chunks = png.chunks();
-        assert_eq!(chunks.len(), 3);
+    fn test_stream_chunk_data_stops_at_iend() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend(b"trailing bytes");
+        let mut reader = std::io::Cursor::new(&bytes[..]);
+        let mut out = Vec::new();
+        Png::stream_chunk_data(&mut reader, "IDAT", &mut out).unwrap();
+        assert_eq!(reader.position() as usize, PNG_FILE.len());
+
+        let truncated = &PNG_FILE[..PNG_FILE.len() - 6];
+        let mut reader = std::io::Cursor::new(truncated);
+        let result = Png::stream_chunk_data(&mut reader, "IDAT", &mut out);
+        assert!(matches!(result, Err(PngError::Malformed(_))));
     }
 
     #[test]
-    fn test_chunk_by_type() {
-        let png = testing_png();
-        let chunk = png.chunk_by_type("FrSt").unwrap();
-        assert_eq!(&chunk.chunk_type().to_string(), "FrSt");
-        assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
+    fn test_stream_chunk_data_not_found() {
+        let mut reader = std::io::Cursor::new(&PNG_FILE[..]);
+        let mut out = Vec::new();
+        assert!(Png::stream_chunk_data(&mut reader, "ruSt", &mut out).is_err());
     }
 
     #[test]
-    fn test_append_chunk() {
+    fn test_remove_chunks_where() {
         let mut png = testing_png();
-        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
-        let chunk = png.chunk_by_type("TeSt").unwrap();
-        assert_eq!(&chunk.chunk_type().to_string(), "TeSt");
-        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+        let removed = png.remove_chunks_where(|chunk| chunk.chunk_type().is_critical());
+        let removed_types = removed
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(removed_types, ["FrSt", "LASt"]);
+        assert_eq!(png.chunks().len(), 1);
+        assert_eq!(&png.chunks()[0].chunk_type().to_string(), "miDl");
     }
 
     #[test]
-    fn test_remove_chunk() {
-        let mut png = testing_png();
-        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
-        png.remove_chunk("TeSt").unwrap();
-        let chunk = png.chunk_by_type("TeSt");
-        assert!(chunk.is_none());
+    fn test_idat_contiguous() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.validate_idat_contiguous().is_ok());
+        assert!(testing_png().validate_idat_contiguous().is_ok());
+
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("IDAT", "first").unwrap(),
+            chunk_from_strings("IDAT", "second").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        assert!(png.validate_idat_contiguous().is_ok());
+    }
+
+    #[test]
+    fn test_idat_not_contiguous() {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("IDAT", "first").unwrap(),
+            chunk_from_strings("teXt", "interloper").unwrap(),
+            chunk_from_strings("IDAT", "second").unwrap(),
+        ]);
+        let error = png.validate_idat_contiguous().unwrap_err().to_string();
+        assert!(error.contains("teXt at index 1"));
+    }
+
+    #[test]
+    fn test_data_uri() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let uri = png.data_uri();
+        let encoded = uri.strip_prefix("data:image/png;base64,").unwrap();
+        let decoded = crate::encoding::base64_decode(encoded).unwrap();
+        assert_eq!(decoded, PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_duplicate_ihdr() {
+        let png = Png::from_chunks(
+            ["IHDR", "IHDR", "IDAT", "IDAT", "IEND"]
+                .iter()
+                .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+                .collect(),
+        );
+        assert_eq!(
+            png.validate_unique_critical().unwrap_err().to_string(),
+            "Duplicate IHDR chunks at indices 0, 1"
+        );
+        let mut png = png;
+        png.remove_chunk_at(0).unwrap();
+        assert!(png.validate_unique_critical().is_ok());
+    }
+
+    fn png_with_color_type(color_type: u8, palette: bool) -> Png {
+        let ihdr = [0, 0, 0, 1, 0, 0, 0, 1, 8, color_type, 0, 0, 0];
+        let mut chunks = vec![Chunk::new(
+            ChunkType::from_str("IHDR").unwrap(),
+            ihdr.to_vec(),
+        )];
+        if palette {
+            chunks.push(Chunk::new(
+                ChunkType::from_str("PLTE").unwrap(),
+                vec![0, 0, 0],
+            ));
+        }
+        chunks.push(chunk_from_strings("IEND", "").unwrap());
+        Png::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_palette_requirements() {
+        assert!(png_with_color_type(3, true).validate_palette().is_ok());
+        assert!(png_with_color_type(2, true).validate_palette().is_ok());
+        assert!(png_with_color_type(2, false).validate_palette().is_ok());
+        assert!(png_with_color_type(0, false).validate_palette().is_ok());
+    }
+
+    #[test]
+    fn test_indexed_without_palette() {
+        assert!(png_with_color_type(3, false).validate_palette().is_err());
+    }
+
+    #[test]
+    fn test_grayscale_with_palette() {
+        assert!(png_with_color_type(0, true).validate_palette().is_err());
+        assert!(png_with_color_type(4, true).validate_palette().is_err());
     }
 
     #[test]
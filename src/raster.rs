@@ -0,0 +1,485 @@
+use crate::{ihdr::Interlace, png::Png, zlib};
+
+/// Decoded pixels, each expanded to 16-bit RGBA so that images stored with
+/// different color types, bit depths or interlacing can be compared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pixels: Vec<[u16; 4]>,
+}
+
+/// The Adam7 passes as (first column, first row, column step, row step)
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+fn channels(color_type: u8) -> crate::Result<usize> {
+    match color_type {
+        0 | 3 => Ok(1),
+        2 => Ok(3),
+        4 => Ok(2),
+        6 => Ok(4),
+        other => Err(format!("Unknown color type {other}").into()),
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reverses the per-scanline filters, `bpp` being the filter's byte distance
+/// to the previous pixel
+fn unfilter(data: &[u8], row_bytes: usize, height: usize, bpp: usize) -> crate::Result<Vec<u8>> {
+    if data.len() < (row_bytes + 1) * height {
+        return Err(format!(
+            "Image data is {} bytes, expected {}",
+            data.len(),
+            (row_bytes + 1) * height
+        )
+        .into());
+    }
+    let mut out = vec![0u8; row_bytes * height];
+    for y in 0..height {
+        let filter = data[y * (row_bytes + 1)];
+        let line = &data[y * (row_bytes + 1) + 1..(y + 1) * (row_bytes + 1)];
+        let (done, rest) = out.split_at_mut(y * row_bytes);
+        let prior = if y == 0 {
+            None
+        } else {
+            Some(&done[(y - 1) * row_bytes..])
+        };
+        let row = &mut rest[..row_bytes];
+        for x in 0..row_bytes {
+            let a = if x >= bpp { row[x - bpp] } else { 0 };
+            let b = prior.map_or(0, |prior| prior[x]);
+            let c = if x >= bpp {
+                prior.map_or(0, |prior| prior[x - bpp])
+            } else {
+                0
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                other => return Err(format!("Unknown filter type {other} on row {y}").into()),
+            };
+            row[x] = line[x].wrapping_add(predicted);
+        }
+    }
+    Ok(out)
+}
+
+/// The first `count` raw samples of a scanline, sub-byte samples shifted down
+fn samples(row: &[u8], bit_depth: u8, count: usize) -> Vec<u16> {
+    match bit_depth {
+        16 => row
+            .chunks_exact(2)
+            .take(count)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect(),
+        8 => row[..count].iter().map(|&byte| byte as u16).collect(),
+        _ => {
+            let depth = bit_depth as usize;
+            (0..count)
+                .map(|i| {
+                    let bit = i * depth;
+                    let byte = row[bit / 8] >> (8 - depth - bit % 8);
+                    (byte & ((1 << depth) - 1) as u8) as u16
+                })
+                .collect()
+        }
+    }
+}
+
+/// What turns raw samples into RGBA: the IHDR format plus PLTE and tRNS
+struct Colors {
+    bit_depth: u8,
+    color_type: u8,
+    palette: Vec<[u8; 3]>,
+    transparency: Vec<u8>,
+}
+
+impl Colors {
+    fn rgba(&self, pixel: &[u16]) -> crate::Result<[u16; 4]> {
+        let max = (1u32 << self.bit_depth) - 1;
+        let scale = |sample: u16| (sample as u32 * 65535 / max) as u16;
+        // For gray and truecolor, tRNS holds one 16-bit sample per channel
+        // naming the single color that is fully transparent
+        let transparent = |samples: &[u16]| {
+            self.transparency.len() == samples.len() * 2
+                && samples.iter().enumerate().all(|(i, &sample)| {
+                    u16::from_be_bytes([self.transparency[2 * i], self.transparency[2 * i + 1]])
+                        == sample
+                })
+        };
+        let alpha = |samples: &[u16]| if transparent(samples) { 0 } else { 65535 };
+        Ok(match self.color_type {
+            0 => {
+                let gray = scale(pixel[0]);
+                [gray, gray, gray, alpha(pixel)]
+            }
+            2 => [
+                scale(pixel[0]),
+                scale(pixel[1]),
+                scale(pixel[2]),
+                alpha(pixel),
+            ],
+            3 => {
+                let index = pixel[0] as usize;
+                let [r, g, b] = *self.palette.get(index).ok_or_else(|| {
+                    format!(
+                        "Palette index {index} is out of range for {} entries",
+                        self.palette.len()
+                    )
+                })?;
+                let a = self.transparency.get(index).copied().unwrap_or(255);
+                [r, g, b, a].map(|sample| sample as u16 * 257)
+            }
+            4 => {
+                let gray = scale(pixel[0]);
+                [gray, gray, gray, scale(pixel[1])]
+            }
+            _ => [
+                scale(pixel[0]),
+                scale(pixel[1]),
+                scale(pixel[2]),
+                scale(pixel[3]),
+            ],
+        })
+    }
+}
+
+impl TryFrom<&Png> for Raster {
+    type Error = crate::Error;
+
+    fn try_from(png: &Png) -> Result<Self, Self::Error> {
+        let ihdr = png.ihdr().ok_or("Missing or invalid IHDR chunk")?;
+        ihdr.validate()?;
+        let passes: &[(usize, usize, usize, usize)] = match ihdr.interlace {
+            Interlace::None => &[(0, 0, 1, 1)],
+            Interlace::Adam7 => &ADAM7,
+            Interlace::Unknown(_) => {
+                return Err(format!("Interlace method {} is not supported", ihdr.interlace).into())
+            }
+        };
+        let channels = channels(ihdr.color_type)?;
+        let pixel_bits = channels * ihdr.bit_depth as usize;
+        let chunk_data = |chunk_type: &str| {
+            png.chunks()
+                .iter()
+                .filter(|chunk| chunk.chunk_type().to_string() == chunk_type)
+                .flat_map(|chunk| chunk.data())
+                .copied()
+                .collect::<Vec<u8>>()
+        };
+        let colors = Colors {
+            bit_depth: ihdr.bit_depth,
+            color_type: ihdr.color_type,
+            palette: chunk_data("PLTE")
+                .chunks_exact(3)
+                .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                .collect(),
+            transparency: chunk_data("tRNS"),
+        };
+        let (width, height) = (ihdr.width as usize, ihdr.height as usize);
+        let too_large = || format!("A {width}x{height} image is too large to decode");
+        // (pass, width, height, bytes per row) of each non-empty pass, since
+        // empty passes have no scanlines, not even filter bytes
+        let mut layout = Vec::new();
+        let mut expected = 0usize;
+        for &pass @ (x0, y0, dx, dy) in passes {
+            let pass_width = width.saturating_sub(x0).div_ceil(dx);
+            let pass_height = height.saturating_sub(y0).div_ceil(dy);
+            if pass_width == 0 || pass_height == 0 {
+                continue;
+            }
+            let row_bytes = pass_width
+                .checked_mul(pixel_bits)
+                .map(|bits| bits.div_ceil(8))
+                .ok_or_else(too_large)?;
+            expected = (row_bytes + 1)
+                .checked_mul(pass_height)
+                .and_then(|bytes| bytes.checked_add(expected))
+                .ok_or_else(too_large)?;
+            layout.push((pass, pass_width, pass_height, row_bytes));
+        }
+        let filtered =
+            zlib::decompress_with_limit(&chunk_data("IDAT"), expected.min(zlib::MAX_OUTPUT))?;
+        if filtered.len() != expected {
+            return Err(format!(
+                "Image data is {} bytes, expected {expected}",
+                filtered.len()
+            )
+            .into());
+        }
+        let count = width.checked_mul(height).ok_or_else(too_large)?;
+        let mut pixels = vec![[0; 4]; count];
+        let mut rest = &filtered[..];
+        for (pass, pass_width, pass_height, row_bytes) in layout {
+            let (x0, y0, dx, dy) = pass;
+            let data = unfilter(rest, row_bytes, pass_height, pixel_bits.div_ceil(8))?;
+            rest = &rest[(row_bytes + 1) * pass_height..];
+            for (y, row) in data.chunks_exact(row_bytes).enumerate() {
+                let samples = samples(row, ihdr.bit_depth, pass_width * channels);
+                for (x, pixel) in samples.chunks_exact(channels).enumerate() {
+                    pixels[(y0 + y * dy) * width + x0 + x * dx] = colors.rgba(pixel)?;
+                }
+            }
+        }
+        Ok(Self {
+            width: ihdr.width,
+            height: ihdr.height,
+            bit_depth: ihdr.bit_depth,
+            color_type: ihdr.color_type,
+            pixels,
+        })
+    }
+}
+
+impl Raster {
+    /// The pixel at (x, y) as 16-bit RGBA
+    fn pixel(&self, x: u32, y: u32) -> [u16; 4] {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// The first pixel, in scanline order, that differs between the rasters
+    /// once both are expanded to RGBA
+    pub fn first_difference(&self, other: &Raster) -> crate::Result<Option<(u32, u32)>> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(format!(
+                "Images have different dimensions: {}x{} vs {}x{}",
+                self.width, self.height, other.width, other.height
+            )
+            .into());
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.pixel(x, y) != other.pixel(x, y) {
+                    return Ok(Some((x, y)));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunk::Chunk, chunk_type::ChunkType};
+    use std::str::FromStr;
+
+    /// Filters each row with the given filter type, the inverse of `unfilter`
+    fn filter(rows: &[&[u8]], filters: &[u8], bpp: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            out.push(filters[y]);
+            for x in 0..row.len() {
+                let a = if x >= bpp { row[x - bpp] } else { 0 };
+                let b = if y > 0 { rows[y - 1][x] } else { 0 };
+                let c = if y > 0 && x >= bpp {
+                    rows[y - 1][x - bpp]
+                } else {
+                    0
+                };
+                let predicted = match filters[y] {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                out.push(row[x].wrapping_sub(predicted));
+            }
+        }
+        out
+    }
+
+    fn rgb_png(rows: &[&[u8]], filters: &[u8]) -> Png {
+        let width = (rows[0].len() / 3) as u32;
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend((rows.len() as u32).to_be_bytes());
+        ihdr.extend([8, 2, 0, 0, 0]);
+        let idat = zlib::compress(&filter(rows, filters, 3));
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), ihdr),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), idat[..10].to_vec()),
+            Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"a\0b".to_vec()),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), idat[10..].to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    const ROWS: [&[u8]; 5] = [
+        &[10, 20, 30, 200, 100, 50],
+        &[11, 22, 33, 190, 90, 40],
+        &[0, 255, 128, 7, 7, 7],
+        &[250, 3, 90, 80, 200, 10],
+        &[1, 2, 3, 4, 5, 6],
+    ];
+
+    /// A PNG with the given IHDR fields and unfiltered-looking scanlines,
+    /// each row already prefixed with its filter byte
+    fn png_with(
+        size: (u32, u32),
+        bit_depth: u8,
+        color_type: u8,
+        interlace: u8,
+        scanlines: &[u8],
+        extra: &[(&str, &[u8])],
+    ) -> Png {
+        let mut ihdr = size.0.to_be_bytes().to_vec();
+        ihdr.extend(size.1.to_be_bytes());
+        ihdr.extend([bit_depth, color_type, 0, 0, interlace]);
+        let mut chunks = vec![Chunk::new(ChunkType::from_str("IHDR").unwrap(), ihdr)];
+        for (chunk_type, data) in extra {
+            chunks.push(Chunk::new(
+                ChunkType::from_str(chunk_type).unwrap(),
+                data.to_vec(),
+            ));
+        }
+        chunks.push(Chunk::new(
+            ChunkType::from_str("IDAT").unwrap(),
+            zlib::compress(scanlines),
+        ));
+        chunks.push(Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()));
+        Png::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_unfilter_every_filter_type() {
+        let filtered = filter(&ROWS, &[0, 1, 2, 3, 4], 3);
+        assert_eq!(unfilter(&filtered, 6, 5, 3).unwrap(), ROWS.concat());
+        let raster = Raster::try_from(&rgb_png(&ROWS, &[0, 1, 2, 3, 4])).unwrap();
+        assert_eq!(raster.pixel(1, 3), [80 * 257, 200 * 257, 10 * 257, 65535]);
+    }
+
+    #[test]
+    fn test_equivalent_encodings_compare_equal() {
+        let a = Raster::try_from(&rgb_png(&ROWS, &[0, 1, 2, 3, 4])).unwrap();
+        let b = Raster::try_from(&rgb_png(&ROWS, &[4, 4, 3, 2, 1])).unwrap();
+        assert_eq!(a.first_difference(&b).unwrap(), None);
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let mut changed = ROWS;
+        changed[2] = &[0, 255, 128, 7, 7, 8];
+        let a = Raster::try_from(&rgb_png(&ROWS, &[0; 5])).unwrap();
+        let b = Raster::try_from(&rgb_png(&changed, &[0; 5])).unwrap();
+        assert_eq!(a.first_difference(&b).unwrap(), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_sub_byte_and_16_bit_samples() {
+        assert_eq!(samples(&[0b01_10_11_00], 2, 3), [1, 2, 3]);
+        assert_eq!(samples(&[0b1010_0000], 1, 3), [1, 0, 1]);
+        assert_eq!(samples(&[1, 2, 3, 4], 16, 2), [258, 772]);
+    }
+
+    #[test]
+    fn test_palette_matches_truecolor() {
+        let palette: &[u8] = &[10, 20, 30, 200, 100, 50];
+        let indexed = png_with(
+            (2, 1),
+            2,
+            3,
+            0,
+            &[0, 0b0001_0000],
+            &[("PLTE", palette), ("tRNS", &[255, 0])],
+        );
+        let rgba = png_with((2, 1), 8, 6, 0, &[0, 10, 20, 30, 255, 200, 100, 50, 0], &[]);
+        let a = Raster::try_from(&indexed).unwrap();
+        let b = Raster::try_from(&rgba).unwrap();
+        assert_eq!(a.first_difference(&b).unwrap(), None);
+
+        let opaque = png_with((2, 1), 2, 3, 0, &[0, 0b0001_0000], &[("PLTE", palette)]);
+        let c = Raster::try_from(&opaque).unwrap();
+        assert_eq!(c.first_difference(&b).unwrap(), Some((1, 0)));
+    }
+
+    #[test]
+    fn test_huge_dimensions_fail_without_allocating() {
+        let huge = png_with((u32::MAX, u32::MAX), 16, 6, 0, &[0; 9], &[]);
+        assert!(Raster::try_from(&huge).is_err());
+        let tall = png_with((1, 1 << 30), 8, 0, 0, &[0; 2], &[]);
+        assert!(Raster::try_from(&tall)
+            .unwrap_err()
+            .to_string()
+            .contains("expected 2147483648"));
+    }
+
+    #[test]
+    fn test_image_data_must_match_the_dimensions() {
+        let short = png_with((2, 1), 8, 0, 0, &[0, 1], &[]);
+        assert!(Raster::try_from(&short).is_err());
+        let long = png_with((2, 1), 8, 0, 0, &[0, 1, 2, 3], &[]);
+        assert!(Raster::try_from(&long).is_err());
+    }
+
+    #[test]
+    fn test_palette_index_out_of_range() {
+        let png = png_with((1, 1), 8, 3, 0, &[0, 2], &[("PLTE", &[0; 6])]);
+        assert!(Raster::try_from(&png).is_err());
+    }
+
+    #[test]
+    fn test_gray_transparency_and_bit_depths() {
+        let gray8 = png_with((2, 1), 8, 0, 0, &[0, 0, 255], &[("tRNS", &[0, 255])]);
+        let gray16 = png_with((2, 1), 16, 0, 0, &[0, 0, 0, 255, 255], &[]);
+        let gray_alpha = png_with((2, 1), 8, 4, 0, &[0, 0, 255, 255, 0], &[]);
+        let gray8 = Raster::try_from(&gray8).unwrap();
+        let gray16 = Raster::try_from(&gray16).unwrap();
+        let gray_alpha = Raster::try_from(&gray_alpha).unwrap();
+        assert_eq!(gray8.pixel(1, 0), [65535, 65535, 65535, 0]);
+        assert_eq!(gray8.first_difference(&gray_alpha).unwrap(), None);
+        assert_eq!(gray8.first_difference(&gray16).unwrap(), Some((1, 0)));
+    }
+
+    #[test]
+    fn test_adam7_matches_non_interlaced() {
+        // A 3x3 image with pixel values 1 to 9: passes 2 and 3 are empty
+        let plain = png_with((3, 3), 8, 0, 0, &[0, 1, 2, 3, 0, 4, 5, 6, 0, 7, 8, 9], &[]);
+        let adam7 = png_with(
+            (3, 3),
+            8,
+            0,
+            1,
+            &[0, 1, 0, 3, 0, 7, 9, 0, 2, 0, 8, 0, 4, 5, 6],
+            &[],
+        );
+        let a = Raster::try_from(&plain).unwrap();
+        let b = Raster::try_from(&adam7).unwrap();
+        assert_eq!(a.first_difference(&b).unwrap(), None);
+    }
+
+    #[test]
+    fn test_different_formats() {
+        let a = Raster::try_from(&rgb_png(&ROWS, &[0; 5])).unwrap();
+        let b = Raster::try_from(&rgb_png(&ROWS[..4], &[0; 4])).unwrap();
+        assert!(a.first_difference(&b).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! A small backtracking regular expression matcher for chunk type patterns.
+//!
+//! Supports literals, `.`, bracket classes such as `[a-z]` and `[^A-Z]`,
+//! `\d`/`\w` and escaped literals, the `*`, `+` and `?` quantifiers, and the
+//! `^`/`$` anchors. Groups and alternation are not supported.
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Atom {
+    Any,
+    Literal(char),
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Literal(literal) => *literal == c,
+            Self::Class { negated, ranges } => {
+                ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repeat {
+    One,
+    ZeroOrOne,
+    ZeroOrMore,
+    OneOrMore,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Piece {
+    atom: Atom,
+    repeat: Repeat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regex {
+    pieces: Vec<Piece>,
+    anchored_start: bool,
+    anchored_end: bool,
+}
+
+impl FromStr for Regex {
+    type Err = super::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars().peekable();
+        let anchored_start = chars.next_if_eq(&'^').is_some();
+        let mut anchored_end = false;
+        let mut pieces = Vec::new();
+        while let Some(c) = chars.next() {
+            let atom = match c {
+                '$' if chars.peek().is_none() => {
+                    anchored_end = true;
+                    break;
+                }
+                '.' => Atom::Any,
+                '\\' => match chars.next().ok_or("Pattern ends with `\\`")? {
+                    'd' => Atom::Class {
+                        negated: false,
+                        ranges: vec![('0', '9')],
+                    },
+                    'w' => Atom::Class {
+                        negated: false,
+                        ranges: vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+                    },
+                    escaped => Atom::Literal(escaped),
+                },
+                '[' => {
+                    let negated = chars.next_if_eq(&'^').is_some();
+                    let mut ranges = Vec::new();
+                    loop {
+                        let lo = match chars.next().ok_or("Unclosed `[` in pattern")? {
+                            ']' if !ranges.is_empty() => break,
+                            '\\' => chars.next().ok_or("Pattern ends with `\\`")?,
+                            lo => lo,
+                        };
+                        let hi = if chars.next_if_eq(&'-').is_some() {
+                            match chars.peek() {
+                                Some(']') | None => {
+                                    ranges.push(('-', '-'));
+                                    lo
+                                }
+                                Some(_) => chars.next().unwrap(),
+                            }
+                        } else {
+                            lo
+                        };
+                        if hi < lo {
+                            return Err(format!("Invalid range `{lo}-{hi}` in pattern").into());
+                        }
+                        ranges.push((lo, hi));
+                    }
+                    Atom::Class { negated, ranges }
+                }
+                '*' | '+' | '?' => {
+                    return Err(format!("Nothing to repeat before `{c}` in pattern").into())
+                }
+                '(' | ')' | '|' | '{' | '}' => {
+                    return Err(format!("Unsupported `{c}` in pattern").into())
+                }
+                literal => Atom::Literal(literal),
+            };
+            let repeat = match chars.next_if(|c| matches!(c, '*' | '+' | '?')) {
+                Some('*') => Repeat::ZeroOrMore,
+                Some('+') => Repeat::OneOrMore,
+                Some('?') => Repeat::ZeroOrOne,
+                _ => Repeat::One,
+            };
+            pieces.push(Piece { atom, repeat });
+        }
+        Ok(Self {
+            pieces,
+            anchored_start,
+            anchored_end,
+        })
+    }
+}
+
+impl Regex {
+    pub fn is_match(&self, s: &str) -> bool {
+        let chars = s.chars().collect::<Vec<char>>();
+        if self.anchored_start {
+            return self.match_here(&self.pieces, &chars);
+        }
+        (0..=chars.len()).any(|start| self.match_here(&self.pieces, &chars[start..]))
+    }
+
+    fn match_here(&self, pieces: &[Piece], chars: &[char]) -> bool {
+        let Some((piece, rest)) = pieces.split_first() else {
+            return !self.anchored_end || chars.is_empty();
+        };
+        let (min, max) = match piece.repeat {
+            Repeat::One => (1, 1),
+            Repeat::ZeroOrOne => (0, 1),
+            Repeat::ZeroOrMore => (0, usize::MAX),
+            Repeat::OneOrMore => (1, usize::MAX),
+        };
+        let available = chars
+            .iter()
+            .take_while(|&&c| piece.atom.matches(c))
+            .take(max)
+            .count();
+        (min..=available)
+            .rev()
+            .any(|taken| self.match_here(rest, &chars[taken..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, s: &str) -> bool {
+        Regex::from_str(pattern).unwrap().is_match(s)
+    }
+
+    #[test]
+    fn test_literal_search() {
+        assert!(is_match("St", "RuSt"));
+        assert!(!is_match("st", "RuSt"));
+    }
+
+    #[test]
+    fn test_anchors() {
+        assert!(is_match("^[a-z]", "ruSt"));
+        assert!(!is_match("^[a-z]", "RuSt"));
+        assert!(is_match("t$", "RuSt"));
+        assert!(!is_match("^Ru$", "RuSt"));
+        assert!(is_match("^RuSt$", "RuSt"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(is_match("^[A-Z][a-z][A-Z][a-z]$", "RuSt"));
+        assert!(is_match("^[^a-z]", "IHDR"));
+        assert!(!is_match("^[^a-z]", "tEXt"));
+        assert!(is_match("^..[X-Z]t$", "tEXt"));
+        assert!(is_match("\\d", "ab1c"));
+        assert!(is_match("^[a-]$", "-"));
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        assert!(!is_match("^I.*D$", "IHDR"));
+        assert!(is_match("^I.*R$", "IHDR"));
+        assert!(is_match("^IH?DR$", "IDR"));
+        assert!(is_match("^a+b$", "aaab"));
+        assert!(!is_match("^a+b$", "b"));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(Regex::from_str("*a").is_err());
+        assert!(Regex::from_str("[a-").is_err());
+        assert!(Regex::from_str("(ab)").is_err());
+        assert!(Regex::from_str("[z-a]").is_err());
+    }
+}
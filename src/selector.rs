@@ -0,0 +1,186 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{chunk_type::ChunkType, png::Png};
+
+/// Picks one chunk out of a file: `tEXt` (the first tEXt chunk), `tEXt:1`
+/// (the second one) or `@5` (the chunk at index 5, counting from IHDR)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkSelector {
+    Type { chunk_type: String, index: usize },
+    Index(usize),
+}
+
+impl FromStr for ChunkSelector {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(index) = s.strip_prefix('@') {
+            let index = index
+                .parse()
+                .map_err(|_| format!("Invalid chunk index in selector {s:?}"))?;
+            return Ok(Self::Index(index));
+        }
+        let (chunk_type, index) = match s.split_once(':') {
+            Some((chunk_type, index)) => {
+                let index = index
+                    .parse()
+                    .map_err(|_| format!("Invalid chunk index in selector {s:?}"))?;
+                (chunk_type, index)
+            }
+            None => (s, 0),
+        };
+        if chunk_type.len() != 4 || !chunk_type.bytes().all(|byte| byte.is_ascii_alphabetic()) {
+            return Err(format!("Invalid chunk type in selector {s:?}").into());
+        }
+        Ok(Self::Type {
+            chunk_type: chunk_type.to_string(),
+            index,
+        })
+    }
+}
+
+impl Display for ChunkSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Type { chunk_type, index } => write!(f, "{chunk_type}:{index}"),
+            Self::Index(index) => write!(f, "@{index}"),
+        }
+    }
+}
+
+impl ChunkSelector {
+    /// The position of the selected chunk in `png.chunks()`, if it exists
+    pub fn resolve(&self, png: &Png) -> Option<usize> {
+        self.resolve_matching(png, false)
+    }
+    /// Like `resolve`, but matching the chunk type regardless of ASCII case
+    /// when `ignore_case`
+    pub fn resolve_matching(&self, png: &Png, ignore_case: bool) -> Option<usize> {
+        match self {
+            Self::Type { chunk_type, index } => png
+                .chunks()
+                .iter()
+                .enumerate()
+                .filter(|(_, chunk)| chunk.has_type(chunk_type, ignore_case))
+                .nth(*index)
+                .map(|(position, _)| position),
+            Self::Index(index) => (*index < png.chunks().len()).then_some(*index),
+        }
+    }
+    /// A test that is true only for the selected chunk when called on each
+    /// chunk type in file order, for `Png::stream_chunk_data_where`
+    pub fn matcher(&self) -> impl FnMut(&ChunkType) -> bool + '_ {
+        let mut seen = 0;
+        move |chunk_type| {
+            let (counts, index) = match self {
+                Self::Type {
+                    chunk_type: wanted,
+                    index,
+                } => (chunk_type.to_string() == *wanted, *index),
+                Self::Index(index) => (true, *index),
+            };
+            if !counts {
+                return false;
+            }
+            seen += 1;
+            seen == index + 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+
+    fn testing_png() -> Png {
+        let chunk = |chunk_type: &str, data: &str| {
+            Chunk::new(
+                ChunkType::from_str(chunk_type).unwrap(),
+                data.as_bytes().to_vec(),
+            )
+        };
+        Png::from_chunks(vec![
+            chunk("IHDR", ""),
+            chunk("tEXt", "first"),
+            chunk("IDAT", ""),
+            chunk("tEXt", "second"),
+            chunk("IEND", ""),
+        ])
+    }
+
+    #[test]
+    fn test_parse_selectors() {
+        assert_eq!(
+            ChunkSelector::from_str("tEXt").unwrap(),
+            ChunkSelector::Type {
+                chunk_type: "tEXt".to_string(),
+                index: 0
+            }
+        );
+        assert_eq!(
+            ChunkSelector::from_str("tEXt:1").unwrap(),
+            ChunkSelector::Type {
+                chunk_type: "tEXt".to_string(),
+                index: 1
+            }
+        );
+        assert_eq!(
+            ChunkSelector::from_str("@5").unwrap(),
+            ChunkSelector::Index(5)
+        );
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        for selector in [
+            "", "tEX", "tEXt:", "tEXt:-1", "tEXt:x", "@", "@x", "t3Xt", "tEXtt",
+        ] {
+            assert!(ChunkSelector::from_str(selector).is_err(), "{selector}");
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for selector in ["tEXt:1", "@5"] {
+            assert_eq!(
+                ChunkSelector::from_str(selector).unwrap().to_string(),
+                selector
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let png = testing_png();
+        let resolve = |s: &str| ChunkSelector::from_str(s).unwrap().resolve(&png);
+        assert_eq!(resolve("tEXt"), Some(1));
+        assert_eq!(resolve("tEXt:1"), Some(3));
+        assert_eq!(resolve("tEXt:2"), None);
+        assert_eq!(resolve("@4"), Some(4));
+        assert_eq!(resolve("@5"), None);
+        assert_eq!(resolve("zTXt"), None);
+    }
+
+    #[test]
+    fn test_matcher_agrees_with_resolve() {
+        let png = testing_png();
+        for s in ["tEXt", "tEXt:1", "tEXt:2", "@4", "@5", "zTXt"] {
+            let selector = ChunkSelector::from_str(s).unwrap();
+            let mut matcher = selector.matcher();
+            let matched: Vec<usize> = (0..png.chunks().len())
+                .filter(|&i| matcher(png.chunks()[i].chunk_type()))
+                .collect();
+            assert_eq!(matched.first().copied(), selector.resolve(&png), "{s}");
+            assert!(matched.len() <= 1, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_ignoring_case() {
+        let png = testing_png();
+        let selector = ChunkSelector::from_str("text:1").unwrap();
+        assert_eq!(selector.resolve(&png), None);
+        assert_eq!(selector.resolve_matching(&png, true), Some(3));
+    }
+}
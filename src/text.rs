@@ -0,0 +1,293 @@
+use crate::{chunk::Chunk, json::Json, zlib};
+
+/// An international text (`iTXt`) chunk payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ITxt {
+    pub keyword: String,
+    pub compressed: bool,
+    pub language: String,
+    pub translated_keyword: String,
+    pub text: String,
+}
+
+/// Keywords are 1-79 bytes of Latin-1
+fn keyword_bytes(keyword: &str) -> crate::Result<Vec<u8>> {
+    let bytes = keyword
+        .chars()
+        .map(|c| match u8::try_from(c) {
+            Ok(byte) if byte != 0 => Ok(byte),
+            _ => Err(format!("Keyword {keyword:?} must be Latin-1 without NUL")),
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    if !(1..=79).contains(&bytes.len()) {
+        return Err(format!("Keyword must be 1-79 bytes, got {}", bytes.len()).into());
+    }
+    Ok(bytes)
+}
+
+/// Splits off bytes up to the next NUL, returning them and the rest
+fn take_field<'a>(data: &'a [u8], name: &str) -> crate::Result<(&'a [u8], &'a [u8])> {
+    let end = data
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or(format!("iTXt {name} is not NUL-terminated"))?;
+    Ok((&data[..end], &data[end + 1..]))
+}
+
+impl TryFrom<&[u8]> for ITxt {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (keyword, rest) = take_field(value, "keyword")?;
+        let (&[compression_flag, compression_method], rest) = rest
+            .split_first_chunk::<2>()
+            .ok_or("iTXt is missing its compression fields")?;
+        let compressed = match compression_flag {
+            0 => false,
+            1 if compression_method == 0 => true,
+            1 => {
+                return Err(format!("Unknown iTXt compression method {compression_method}").into())
+            }
+            other => return Err(format!("Invalid iTXt compression flag {other}").into()),
+        };
+        let (language, rest) = take_field(rest, "language tag")?;
+        let (translated_keyword, text) = take_field(rest, "translated keyword")?;
+        let text = if compressed {
+            zlib::decompress(text)?
+        } else {
+            text.to_vec()
+        };
+        Ok(Self {
+            keyword: keyword.iter().map(|&byte| byte as char).collect(),
+            compressed,
+            language: String::from_utf8(language.to_vec())?,
+            translated_keyword: String::from_utf8(translated_keyword.to_vec())?,
+            text: String::from_utf8(text)?,
+        })
+    }
+}
+
+impl ITxt {
+    pub fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        if !self.language.is_ascii() {
+            return Err(format!("Language tag {:?} must be ASCII", self.language).into());
+        }
+        if self.translated_keyword.contains('\0') || self.text.contains('\0') {
+            return Err("iTXt translated keyword and text must not contain NUL".into());
+        }
+        let mut bytes = keyword_bytes(&self.keyword)?;
+        bytes.push(0);
+        bytes.extend([u8::from(self.compressed), 0]);
+        bytes.extend(self.language.as_bytes());
+        bytes.push(0);
+        bytes.extend(self.translated_keyword.as_bytes());
+        bytes.push(0);
+        if self.compressed {
+            bytes.extend(zlib::compress(self.text.as_bytes()));
+        } else {
+            bytes.extend(self.text.as_bytes());
+        }
+        Ok(bytes)
+    }
+}
+
+/// A `tEXt` chunk payload, `keyword\0text` in Latin-1
+pub fn text_bytes(keyword: &str, text: &str) -> crate::Result<Vec<u8>> {
+    let mut bytes = keyword_bytes(keyword)?;
+    bytes.push(0);
+    for c in text.chars() {
+        match u8::try_from(c) {
+            Ok(byte) if byte != 0 => bytes.push(byte),
+            _ => {
+                return Err(
+                    format!("tEXt text must be Latin-1 without NUL, found {c:?}; use iTXt").into(),
+                )
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// A decoded tEXt, zTXt or iTXt chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEntry {
+    pub keyword: String,
+    pub value: String,
+    pub chunk_type: String,
+    pub language: Option<String>,
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+impl TryFrom<&Chunk> for TextEntry {
+    type Error = crate::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        let chunk_type = chunk.chunk_type().to_string();
+        let entry = |keyword: String, value: String, language: Option<String>| Self {
+            keyword,
+            value,
+            chunk_type: chunk_type.clone(),
+            language,
+        };
+        match chunk_type.as_str() {
+            "tEXt" => {
+                let (keyword, text) = chunk.split_keyword().ok_or("tEXt has no keyword")?;
+                Ok(entry(latin1(keyword), latin1(text), None))
+            }
+            "zTXt" => {
+                let (keyword, rest) = chunk.split_keyword().ok_or("zTXt has no keyword")?;
+                let (&method, compressed) = rest
+                    .split_first()
+                    .ok_or("zTXt is missing its compression method")?;
+                if method != 0 {
+                    return Err(format!("Unknown zTXt compression method {method}").into());
+                }
+                let text = zlib::decompress(compressed)?;
+                Ok(entry(latin1(keyword), latin1(&text), None))
+            }
+            "iTXt" => {
+                let itxt = ITxt::try_from(chunk.data())?;
+                let language = Some(itxt.language).filter(|language| !language.is_empty());
+                Ok(entry(itxt.keyword, itxt.text, language))
+            }
+            other => Err(format!("{other} is not a text chunk").into()),
+        }
+    }
+}
+
+impl TextEntry {
+    pub fn to_json(&self) -> Json {
+        let mut fields = vec![
+            ("keyword".to_string(), Json::from(self.keyword.as_str())),
+            ("value".to_string(), Json::from(self.value.as_str())),
+            ("type".to_string(), Json::from(self.chunk_type.as_str())),
+        ];
+        if let Some(language) = &self.language {
+            fields.push(("language".to_string(), Json::from(language.as_str())));
+        }
+        Json::Object(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_itxt(compressed: bool) -> ITxt {
+        ITxt {
+            keyword: "Title".to_string(),
+            compressed,
+            language: "ja".to_string(),
+            translated_keyword: "タイトル".to_string(),
+            text: "富士山の写真 ".repeat(20),
+        }
+    }
+
+    #[test]
+    fn test_uncompressed_round_trip() {
+        let itxt = testing_itxt(false);
+        let bytes = itxt.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"Title\0\0\0ja\0"));
+        assert_eq!(ITxt::try_from(&bytes[..]).unwrap(), itxt);
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let itxt = testing_itxt(true);
+        let bytes = itxt.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"Title\0\x01\0ja\0"));
+        assert!(bytes.len() < testing_itxt(false).to_bytes().unwrap().len());
+        assert_eq!(ITxt::try_from(&bytes[..]).unwrap(), itxt);
+    }
+
+    #[test]
+    fn test_empty_optional_fields() {
+        let bytes = b"Comment\0\0\0\0\0hello";
+        let itxt = ITxt::try_from(&bytes[..]).unwrap();
+        assert_eq!(itxt.keyword, "Comment");
+        assert_eq!(itxt.language, "");
+        assert_eq!(itxt.translated_keyword, "");
+        assert_eq!(itxt.text, "hello");
+    }
+
+    #[test]
+    fn test_malformed_itxt() {
+        assert!(ITxt::try_from(&b"Title"[..]).is_err());
+        assert!(ITxt::try_from(&b"Title\0"[..]).is_err());
+        assert!(ITxt::try_from(&b"Title\0\x02\0\0\0text"[..]).is_err());
+        assert!(ITxt::try_from(&b"Title\0\0\0en"[..]).is_err());
+    }
+
+    fn text_chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        use crate::chunk_type::ChunkType;
+        use std::str::FromStr;
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    #[test]
+    fn test_text_entries() {
+        let text = TextEntry::try_from(&text_chunk("tEXt", b"Author\0Ren\xe9")).unwrap();
+        assert_eq!(text.keyword, "Author");
+        assert_eq!(text.value, "René");
+        assert_eq!(text.language, None);
+
+        let mut ztxt = b"Comment\0\0".to_vec();
+        ztxt.extend(zlib::compress(b"squeezed"));
+        let ztxt = TextEntry::try_from(&text_chunk("zTXt", &ztxt)).unwrap();
+        assert_eq!(ztxt.value, "squeezed");
+        assert_eq!(ztxt.chunk_type, "zTXt");
+
+        let itxt =
+            TextEntry::try_from(&text_chunk("iTXt", &testing_itxt(true).to_bytes().unwrap()))
+                .unwrap();
+        assert_eq!(itxt.language.as_deref(), Some("ja"));
+        assert!(TextEntry::try_from(&text_chunk("IDAT", b"")).is_err());
+    }
+
+    #[test]
+    fn test_text_bytes_round_trip() {
+        let bytes = text_bytes("Author", "René").unwrap();
+        assert_eq!(bytes, b"Author\0Ren\xe9");
+        let entry = TextEntry::try_from(&text_chunk("tEXt", &bytes)).unwrap();
+        assert_eq!(
+            (entry.keyword.as_str(), entry.value.as_str()),
+            ("Author", "René")
+        );
+        assert!(text_bytes("Title", "富士山").is_err());
+        assert!(text_bytes("Title", "a\0b").is_err());
+        assert!(text_bytes("", "text").is_err());
+    }
+
+    #[test]
+    fn test_text_entry_json() {
+        let text = TextEntry::try_from(&text_chunk("tEXt", b"Title\0A \"quoted\" title")).unwrap();
+        assert_eq!(
+            text.to_json().to_string(),
+            r#"{"keyword":"Title","value":"A \"quoted\" title","type":"tEXt"}"#
+        );
+        let itxt = TextEntry::try_from(&text_chunk("iTXt", b"Title\0\0\0en\0\0hi")).unwrap();
+        assert_eq!(
+            itxt.to_json().to_string(),
+            r#"{"keyword":"Title","value":"hi","type":"iTXt","language":"en"}"#
+        );
+    }
+
+    #[test]
+    fn test_nul_in_text_rejected() {
+        let mut itxt = testing_itxt(false);
+        itxt.text = "before\0after".to_string();
+        assert!(itxt.to_bytes().is_err());
+    }
+
+    #[test]
+    fn test_invalid_keyword() {
+        let mut itxt = testing_itxt(false);
+        itxt.keyword = "タイトル".to_string();
+        assert!(itxt.to_bytes().is_err());
+        itxt.keyword = String::new();
+        assert!(itxt.to_bytes().is_err());
+    }
+}
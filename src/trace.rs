@@ -0,0 +1,43 @@
+//! Tracing of parse steps on stderr, off unless enabled with `set_enabled`
+//! (the CLI does so for `--trace` or `PNGME_TRACE=1`).
+
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Prints `message` as a `trace:` line when tracing is enabled
+pub fn trace(message: impl Display) {
+    if is_enabled() {
+        eprintln!("trace: {message}");
+    }
+}
+
+/// What the chunk header at `offset` declares, traced before the chunk is
+/// checked so the last line shows where parsing stopped
+pub fn chunk_header(bytes: &[u8], offset: usize) {
+    if !is_enabled() {
+        return;
+    }
+    match bytes.get(..8) {
+        Some(header) => trace(format_args!(
+            "offset {offset}: {} chunk, length {}",
+            String::from_utf8_lossy(&header[4..]).escape_debug(),
+            u32::from_be_bytes(header[..4].try_into().unwrap())
+        )),
+        None => trace(format_args!(
+            "offset {offset}: {} bytes left, too few for a chunk header",
+            bytes.len()
+        )),
+    }
+}
@@ -0,0 +1,185 @@
+//! Reads single entries out of zip archives, for `archive.zip!inner/image.png`
+//! paths. Only stored and deflated entries are supported.
+
+use std::path::{Path, PathBuf};
+
+use crate::{chunk::CRC, zlib};
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_ENTRY: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+
+fn u16_at(data: &[u8], offset: usize) -> crate::Result<u16> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or("Unexpected end of zip archive")?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> crate::Result<u32> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or("Unexpected end of zip archive")?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Splits `archive.zip!inner/image.png` into the archive and entry name, when
+/// the part before the `!` is an existing file
+pub fn split_entry_path(path: &Path) -> Option<(PathBuf, String)> {
+    let path = path.to_str()?;
+    let (archive, entry) = path.split_once('!')?;
+    let archive = PathBuf::from(archive);
+    (archive.is_file() && !entry.is_empty()).then(|| (archive, entry.to_string()))
+}
+
+/// The uncompressed contents of the entry called `name`
+pub fn read_entry(archive: &[u8], name: &str) -> crate::Result<Vec<u8>> {
+    let end = archive
+        .len()
+        .checked_sub(22)
+        .and_then(|last| {
+            (0..=last)
+                .rev()
+                .find(|&i| u32_at(archive, i).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+        })
+        .ok_or("Not a zip archive")?;
+    let entries = u16_at(archive, end + 10)?;
+    let mut offset = u32_at(archive, end + 16)? as usize;
+    for _ in 0..entries {
+        if u32_at(archive, offset)? != CENTRAL_DIRECTORY_ENTRY {
+            return Err("Corrupt zip central directory".into());
+        }
+        let name_len = u16_at(archive, offset + 28)? as usize;
+        let entry_name = archive
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or("Unexpected end of zip archive")?;
+        if entry_name == name.as_bytes() {
+            let method = u16_at(archive, offset + 10)?;
+            let crc = u32_at(archive, offset + 16)?;
+            let compressed_size = u32_at(archive, offset + 20)? as usize;
+            let local = u32_at(archive, offset + 42)? as usize;
+            return read_local_entry(archive, local, method, compressed_size, crc);
+        }
+        offset += 46
+            + name_len
+            + u16_at(archive, offset + 30)? as usize
+            + u16_at(archive, offset + 32)? as usize;
+    }
+    Err(format!("No entry named {name:?} in zip archive").into())
+}
+
+fn read_local_entry(
+    archive: &[u8],
+    offset: usize,
+    method: u16,
+    compressed_size: usize,
+    crc: u32,
+) -> crate::Result<Vec<u8>> {
+    if u32_at(archive, offset)? != LOCAL_FILE_HEADER {
+        return Err("Corrupt zip local file header".into());
+    }
+    let start = offset
+        + 30
+        + u16_at(archive, offset + 26)? as usize
+        + u16_at(archive, offset + 28)? as usize;
+    let compressed = archive
+        .get(start..start + compressed_size)
+        .ok_or("Unexpected end of zip archive")?;
+    let data = match method {
+        0 => compressed.to_vec(),
+        8 => zlib::inflate(compressed)?.0,
+        other => return Err(format!("Unsupported zip compression method {other}").into()),
+    };
+    if CRC.checksum(&data) != crc {
+        return Err("Zip entry CRC mismatch".into());
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal archive with one entry per (name, method, data) triple
+    fn testing_zip(entries: &[(&str, u16, &[u8])]) -> Vec<u8> {
+        let mut zip = Vec::new();
+        let mut central = Vec::new();
+        for &(name, method, data) in entries {
+            let compressed = match method {
+                8 => zlib::deflate(data),
+                _ => data.to_vec(),
+            };
+            let crc = CRC.checksum(data);
+            let local_offset = zip.len() as u32;
+            zip.extend(LOCAL_FILE_HEADER.to_le_bytes());
+            zip.extend([20, 0, 0, 0]);
+            zip.extend(method.to_le_bytes());
+            zip.extend([0; 4]);
+            zip.extend(crc.to_le_bytes());
+            zip.extend((compressed.len() as u32).to_le_bytes());
+            zip.extend((data.len() as u32).to_le_bytes());
+            zip.extend((name.len() as u16).to_le_bytes());
+            zip.extend([0, 0]);
+            zip.extend(name.as_bytes());
+            zip.extend(&compressed);
+
+            central.extend(CENTRAL_DIRECTORY_ENTRY.to_le_bytes());
+            central.extend([20, 0, 20, 0, 0, 0]);
+            central.extend(method.to_le_bytes());
+            central.extend([0; 4]);
+            central.extend(crc.to_le_bytes());
+            central.extend((compressed.len() as u32).to_le_bytes());
+            central.extend((data.len() as u32).to_le_bytes());
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend([0; 12]);
+            central.extend(local_offset.to_le_bytes());
+            central.extend(name.as_bytes());
+        }
+        let central_offset = zip.len() as u32;
+        zip.extend(&central);
+        zip.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        zip.extend([0; 4]);
+        zip.extend((entries.len() as u16).to_le_bytes());
+        zip.extend((entries.len() as u16).to_le_bytes());
+        zip.extend((central.len() as u32).to_le_bytes());
+        zip.extend(central_offset.to_le_bytes());
+        zip.extend([0, 0]);
+        zip
+    }
+
+    #[test]
+    fn test_read_stored_and_deflated_entries() {
+        let text = b"an image would go here ".repeat(10);
+        let zip = testing_zip(&[("a.txt", 0, b"stored"), ("dir/b.png", 8, &text)]);
+        assert_eq!(read_entry(&zip, "a.txt").unwrap(), b"stored");
+        assert_eq!(read_entry(&zip, "dir/b.png").unwrap(), text);
+    }
+
+    #[test]
+    fn test_missing_entry_and_bad_archive() {
+        let zip = testing_zip(&[("a.txt", 0, b"stored")]);
+        assert!(read_entry(&zip, "b.txt").is_err());
+        assert!(read_entry(b"not a zip", "a.txt").is_err());
+        assert!(read_entry(&zip[..zip.len() - 30], "a.txt").is_err());
+    }
+
+    #[test]
+    fn test_corrupt_entry_crc() {
+        let mut zip = testing_zip(&[("a.txt", 0, b"stored")]);
+        zip[30 + 5] ^= 1;
+        assert!(read_entry(&zip, "a.txt").is_err());
+    }
+
+    #[test]
+    fn test_split_entry_path() {
+        assert_eq!(split_entry_path(Path::new("missing.zip!a.png")), None);
+        let archive = std::env::temp_dir().join("pngme-split-entry-test.zip");
+        std::fs::write(&archive, testing_zip(&[])).unwrap();
+        let path = format!("{}!inner/a.png", archive.display());
+        assert_eq!(
+            split_entry_path(Path::new(&path)),
+            Some((archive.clone(), "inner/a.png".to_string()))
+        );
+        std::fs::remove_file(archive).unwrap();
+    }
+}
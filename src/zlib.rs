@@ -0,0 +1,510 @@
+//! zlib (RFC 1950) streams over DEFLATE (RFC 1951).
+//!
+//! Decompression handles stored, fixed and dynamic Huffman blocks.
+//! Compression emits a single fixed-Huffman block with LZ77 matching, which
+//! is plenty for text payloads.
+
+/// The most `decompress` and `inflate` will produce, so a small crafted
+/// stream can't expand to fill memory
+pub const MAX_OUTPUT: usize = 1 << 30;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 128;
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+    fn bits(&mut self, n: u32) -> crate::Result<u32> {
+        while self.bit_count < n {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or("Unexpected end of deflate stream")?;
+            self.pos += 1;
+            self.bit_buf |= u32::from(byte) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buf & ((1u64 << n) - 1) as u32;
+        self.bit_buf = self.bit_buf.checked_shr(n).unwrap_or(0);
+        self.bit_count -= n;
+        Ok(value)
+    }
+    fn align_to_byte(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+}
+
+/// Canonical Huffman decoding table, stored as code counts per length and
+/// symbols ordered by code
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> crate::Result<Self> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err("Over-subscribed Huffman code".into());
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+    fn decode(&self, reader: &mut BitReader) -> crate::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(count);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Invalid Huffman code in deflate stream".into())
+    }
+}
+
+fn fixed_tables() -> crate::Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> crate::Result<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let dist_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_table = Huffman::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + dist_count);
+    while lengths.len() < literal_count + dist_count {
+        let (value, repeat) = match code_length_table.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or("Repeat with no previous length")?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() != literal_count + dist_count {
+        return Err("Code lengths overrun the table".into());
+    }
+    if lengths[256] == 0 {
+        return Err("Missing end-of-block code".into());
+    }
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    limit: usize,
+) -> crate::Result<()> {
+    loop {
+        if out.len() > limit {
+            return Err(over_limit(limit));
+        }
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err("Invalid length code in deflate stream".into());
+                }
+                let length = LENGTH_BASE[index] as usize
+                    + reader.bits(u32::from(LENGTH_EXTRA[index]))? as usize;
+                let index = distances.decode(reader)? as usize;
+                if index >= DIST_BASE.len() {
+                    return Err("Invalid distance code in deflate stream".into());
+                }
+                let distance =
+                    DIST_BASE[index] as usize + reader.bits(u32::from(DIST_EXTRA[index]))? as usize;
+                if distance > out.len() {
+                    return Err("Distance reaches before start of output".into());
+                }
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+fn over_limit(limit: usize) -> crate::Error {
+    format!("Decompressed data is over the limit of {limit} bytes").into()
+}
+
+/// Decompresses a raw DEFLATE stream, returning the output and the number of
+/// input bytes consumed
+pub fn inflate(data: &[u8]) -> crate::Result<(Vec<u8>, usize)> {
+    inflate_with_limit(data, MAX_OUTPUT)
+}
+
+/// Like `inflate`, but failing once the output would pass `limit` bytes
+pub fn inflate_with_limit(data: &[u8], limit: usize) -> crate::Result<(Vec<u8>, usize)> {
+    let mut reader = BitReader::new(data);
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let header = data
+                    .get(reader.pos..reader.pos + 4)
+                    .ok_or("Unexpected end of deflate stream")?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                let inverse = u16::from_le_bytes([header[2], header[3]]);
+                if length != !inverse {
+                    return Err("Stored block length check failed".into());
+                }
+                reader.pos += 4;
+                let block = data
+                    .get(reader.pos..reader.pos + length as usize)
+                    .ok_or("Unexpected end of deflate stream")?;
+                if out.len() + block.len() > limit {
+                    return Err(over_limit(limit));
+                }
+                out.extend_from_slice(block);
+                reader.pos += length as usize;
+            }
+            1 => {
+                let (literals, distances) = fixed_tables()?;
+                inflate_block(&mut reader, &mut out, &literals, &distances, limit)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &literals, &distances, limit)?;
+            }
+            _ => return Err("Invalid deflate block type".into()),
+        }
+        if out.len() > limit {
+            return Err(over_limit(limit));
+        }
+        if last {
+            return Ok((out, reader.pos));
+        }
+    }
+}
+
+/// Decompresses a zlib stream, up to `MAX_OUTPUT` bytes
+pub fn decompress(data: &[u8]) -> crate::Result<Vec<u8>> {
+    decompress_with_limit(data, MAX_OUTPUT)
+}
+
+/// Like `decompress`, but failing once the output would pass `limit` bytes
+pub fn decompress_with_limit(data: &[u8], limit: usize) -> crate::Result<Vec<u8>> {
+    if data.len() < 6 {
+        return Err("zlib stream too short".into());
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return Err("Invalid zlib header".into());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib streams with a preset dictionary are not supported".into());
+    }
+    let (out, consumed) = inflate_with_limit(&data[2..], limit)?;
+    let trailer = data
+        .get(2 + consumed..2 + consumed + 4)
+        .ok_or("zlib stream is missing its Adler-32 checksum")?;
+    if u32::from_be_bytes(trailer.try_into().unwrap()) != adler32(&out) {
+        return Err("zlib Adler-32 checksum mismatch".into());
+    }
+    Ok(out)
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, n: u32) {
+        self.bit_buf |= value << self.bit_count;
+        self.bit_count += n;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+    /// Huffman codes are packed starting from their most significant bit
+    fn code(&mut self, code: u32, length: u32) {
+        self.bits(code.reverse_bits() >> (32 - length), length);
+    }
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.out.push(self.bit_buf as u8);
+        }
+        self.out
+    }
+}
+
+fn fixed_literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.code(0x30 + symbol, 8),
+        144..=255 => writer.code(0x190 + symbol - 144, 9),
+        256..=279 => writer.code(symbol - 256, 7),
+        _ => writer.code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn fixed_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let index = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base as usize <= length)
+        .unwrap();
+    fixed_literal(writer, 257 + index as u32);
+    writer.bits(
+        (length - LENGTH_BASE[index] as usize) as u32,
+        u32::from(LENGTH_EXTRA[index]),
+    );
+    let index = DIST_BASE
+        .iter()
+        .rposition(|&base| base as usize <= distance)
+        .unwrap();
+    writer.code(index as u32, 5);
+    writer.bits(
+        (distance - DIST_BASE[index] as usize) as u32,
+        u32::from(DIST_EXTRA[index]),
+    );
+}
+
+fn hash(data: &[u8]) -> usize {
+    ((usize::from(data[0]) << 10) ^ (usize::from(data[1]) << 5) ^ usize::from(data[2])) & 0x7fff
+}
+
+/// Compresses into a raw DEFLATE stream
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        out: Vec::new(),
+        bit_buf: 0,
+        bit_count: 0,
+    };
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+
+    // Most recent position for each hash, and the previous position with the
+    // same hash for each position in the window
+    let mut head = vec![usize::MAX; 0x8000];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |head: &mut [usize], prev: &mut [usize], pos: usize| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(&data[pos..]);
+            prev[pos % WINDOW_SIZE] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let max_length = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash(&data[pos..])];
+            let mut chain = 0;
+            while candidate != usize::MAX && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let length = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best.0 {
+                    best = (length, pos - candidate);
+                    if length == max_length {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+        let (length, distance) = best;
+        if length >= MIN_MATCH {
+            fixed_match(&mut writer, length, distance);
+            for i in pos..pos + length {
+                insert(&mut head, &mut prev, i);
+            }
+            pos += length;
+        } else {
+            fixed_literal(&mut writer, u32::from(data[pos]));
+            insert(&mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+    fixed_literal(&mut writer, 256);
+    writer.finish()
+}
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x9c];
+    out.extend(deflate(data));
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn test_decompress_stored() {
+        let stream = [
+            0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c', 0x02, 0x4d, 0x01, 0x27,
+        ];
+        assert_eq!(decompress(&stream).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_decompress_zlib_output() {
+        // zlib.compress(b"hello hello hello hello, world!") from Python
+        let stream = [
+            0x78, 0x9c, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x75, 0x14, 0xca,
+            0xf3, 0x8b, 0x72, 0x52, 0x14, 0x01, 0xb8, 0xb5, 0x0b, 0x46,
+        ];
+        assert_eq!(
+            decompress(&stream).unwrap(),
+            b"hello hello hello hello, world!"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let inputs = [
+            Vec::new(),
+            b"a".to_vec(),
+            "café 🎉 ".repeat(200).into_bytes(),
+            (0..=255u8).cycle().take(70000).collect(),
+        ];
+        for input in inputs {
+            assert_eq!(decompress(&compress(&input)).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_compress_shrinks_repetitive_input() {
+        let input = "the same words again and again ".repeat(100);
+        assert!(compress(input.as_bytes()).len() < input.len() / 10);
+    }
+
+    #[test]
+    fn test_decompress_with_limit() {
+        let stream = compress(&[0; 10000]);
+        assert_eq!(decompress_with_limit(&stream, 10000).unwrap().len(), 10000);
+        assert!(decompress_with_limit(&stream, 9999)
+            .unwrap_err()
+            .to_string()
+            .contains("over the limit of 9999 bytes"));
+        let stored = [
+            0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c', 0x02, 0x4d, 0x01, 0x27,
+        ];
+        assert!(decompress_with_limit(&stored, 2).is_err());
+    }
+
+    #[test]
+    fn test_decompress_rejects_corruption() {
+        let mut stream = compress(b"some text to corrupt");
+        let last = stream.len() - 1;
+        stream[last] ^= 1;
+        assert!(decompress(&stream).is_err());
+        assert!(decompress(&[0x78, 0x9c]).is_err());
+        assert!(decompress(&[0x00; 8]).is_err());
+    }
+}
@@ -0,0 +1,1558 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+const DICE: &[u8] = include_bytes!("fixtures/dice.png");
+
+fn pngme_with_stdin(args: &[&str], stdin: &[u8]) -> Output {
+    pngme_with_env(args, stdin, &[])
+}
+
+fn pngme_with_env(args: &[&str], stdin: &[u8], env: &[(&str, &str)]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pngme"))
+        .args(args)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Commands that fail early exit without reading stdin, closing the pipe
+    let _ = child.stdin.take().unwrap().write_all(stdin);
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn print_reads_png_from_stdin() {
+    let output = pngme_with_stdin(&["print", "-"], DICE);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Image: 50x50"));
+    assert!(stdout.contains("chunk_type: RuSt"));
+}
+
+#[test]
+fn encode_from_stdin_requires_output_file() {
+    let output = pngme_with_stdin(&["encode", "-", "ruSt", "hi"], DICE);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("pass an output file"));
+}
+
+#[test]
+fn encode_writes_png_to_stdout() {
+    let output = pngme_with_stdin(&["encode", "-", "ruSt", "piped", "-"], DICE);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    let chunk = png.chunk_by_type("ruSt").unwrap();
+    assert_eq!(chunk.data_as_string().unwrap(), "piped");
+}
+
+#[test]
+fn encode_index_inserts_at_position_or_fails_out_of_range() {
+    let output = pngme_with_stdin(&["encode", "-", "ruSt", "hi", "-", "--index", "1"], DICE);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(png.chunks()[1].chunk_type().to_string(), "ruSt");
+
+    let output = pngme_with_stdin(&["encode", "-", "ruSt", "hi", "-", "--index", "99"], DICE);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("out of range"));
+}
+
+#[test]
+fn split_idat_then_merge_idat_restores_idat_data() {
+    let original = pngme::png::Png::try_from(DICE).unwrap();
+    let split = pngme_with_stdin(
+        &["split-idat", "-", "--size", "1000", "--output-file", "-"],
+        DICE,
+    );
+    assert!(split.status.success());
+    let png = pngme::png::Png::try_from(&split.stdout[..]).unwrap();
+    assert!(png.chunks_by_type("IDAT").len() > 1);
+
+    let merged = pngme_with_stdin(&["merge-idat", "-", "--output-file", "-"], &split.stdout);
+    assert!(merged.status.success());
+    let png = pngme::png::Png::try_from(&merged.stdout[..]).unwrap();
+    assert_eq!(
+        png.chunk_by_type("IDAT").unwrap().data(),
+        original.chunk_by_type("IDAT").unwrap().data()
+    );
+}
+
+/// Bytes that aren't valid UTF-8, so they can't be passed as a plain message
+const BINARY: &[u8] = &[0, 159, 146, 150, 255, b'\n'];
+
+#[test]
+fn encode_data_file_round_trips_binary_data() {
+    let data_file = std::env::temp_dir().join("pngme-data-file-test.bin");
+    std::fs::write(&data_file, BINARY).unwrap();
+    let output = pngme_with_stdin(
+        &[
+            "encode",
+            "-",
+            "ruSt",
+            "--data-file",
+            data_file.to_str().unwrap(),
+            "-",
+        ],
+        DICE,
+    );
+    std::fs::remove_file(&data_file).unwrap();
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), BINARY);
+
+    let decoded = pngme_with_stdin(&["decode", "-", "ruSt", "--base64"], &output.stdout);
+    assert!(decoded.status.success());
+    assert_eq!(String::from_utf8(decoded.stdout).unwrap(), "AJ+Slv8K\n");
+}
+
+#[test]
+fn encode_base64_round_trips_through_decode_base64() {
+    let output = pngme_with_stdin(&["encode", "-", "ruSt", "AJ+Slv8K", "-", "--base64"], DICE);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), BINARY);
+
+    let decoded = pngme_with_stdin(&["decode", "-", "ruSt", "--base64"], &output.stdout);
+    assert_eq!(String::from_utf8(decoded.stdout).unwrap(), "AJ+Slv8K\n");
+    let plain = pngme_with_stdin(&["decode", "-", "ruSt"], &output.stdout);
+    assert!(!plain.status.success());
+}
+
+#[test]
+fn verify_prints_ok_or_every_problem() {
+    let output = pngme_with_stdin(&["verify", "-", "--quiet"], DICE);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "OK\n");
+
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    png.remove_chunk("IEND").unwrap();
+    let mut bytes = png.as_bytes();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    let output = pngme_with_stdin(&["verify", "-", "--quiet"], &bytes);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("has an invalid CRC"));
+    assert!(stdout.contains("expected IEND as the last chunk"));
+    assert!(!stdout.contains("OK"));
+}
+
+#[test]
+fn verify_warns_about_unknown_critical_chunks_unless_strict() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    png.append_chunk(pngme::chunk::Chunk::new(
+        "ZZZZ".parse().unwrap(),
+        b"opaque".to_vec(),
+    ));
+    let bytes = png.as_bytes();
+    let output = pngme_with_stdin(&["verify", "-"], &bytes);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().ends_with("OK\n"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("warning: unknown critical chunk ZZZZ at index 6"));
+    let output = pngme_with_stdin(&["verify", "-", "--quiet"], &bytes);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let output = pngme_with_stdin(&["verify", "-", "--quiet", "--strict"], &bytes);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // The fixture's own RuSt chunk is critical too
+    assert_eq!(
+        stdout,
+        "unknown critical chunk RuSt at index 5\nunknown critical chunk ZZZZ at index 6\n"
+    );
+}
+
+#[test]
+fn limit_chunk_size_rejects_chunks_that_cannot_be_split() {
+    let output = pngme_with_stdin(
+        &[
+            "encode",
+            "-",
+            "ruSt",
+            "too long",
+            "-",
+            "--limit-chunk-size",
+            "4",
+        ],
+        DICE,
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("ruSt chunk holds 8 bytes of data, over the chunk size limit of 4"));
+
+    let output = pngme_with_stdin(
+        &[
+            "split-idat",
+            "-",
+            "--limit-chunk-size",
+            "10",
+            "--output-file",
+            "-",
+        ],
+        DICE,
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("IHDR chunk holds 13 bytes"));
+
+    let output = pngme_with_stdin(
+        &[
+            "split-idat",
+            "-",
+            "--limit-chunk-size",
+            "1000",
+            "--output-file",
+            "-",
+        ],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert!(png.chunks().iter().all(|chunk| chunk.length() <= 1000));
+}
+
+#[test]
+fn encode_chunk_size_splits_and_decode_join_reassembles() {
+    let message = "a message that is several times longer than one chunk";
+    let output = pngme_with_stdin(
+        &["encode", "-", "ruSt", message, "-", "--chunk-size", "20"],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    let pieces = png.chunks_by_type("ruSt");
+    assert_eq!(pieces.len(), message.len().div_ceil(8));
+    assert!(pieces.iter().all(|piece| piece.length() <= 20));
+    assert_eq!(
+        png.chunks().last().unwrap().chunk_type().to_string(),
+        "IEND"
+    );
+
+    let decoded = pngme_with_stdin(&["decode", "-", "ruSt", "--all", "--join"], &output.stdout);
+    assert!(decoded.status.success());
+    assert_eq!(
+        String::from_utf8(decoded.stdout).unwrap(),
+        format!("{message}\n")
+    );
+}
+
+#[test]
+fn fix_crc_makes_a_hand_edited_file_parse_again() {
+    let mut bytes = DICE.to_vec();
+    // The first byte of IHDR's data, the high byte of the width
+    bytes[16] ^= 1;
+    assert!(pngme::png::Png::try_from(&bytes[..]).is_err());
+    let output = pngme_with_stdin(&["fix-crc", "-", "--output-file", "-"], &bytes);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Fixed 1 chunk CRCs\n"
+    );
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(png.chunks()[0].data()[0], 1);
+}
+
+#[test]
+fn remove_writes_png_to_stdout() {
+    let output = pngme_with_stdin(
+        &[
+            "remove",
+            "-",
+            "RuSt",
+            "--show-changes",
+            "--output-file",
+            "-",
+        ],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert!(png.chunk_by_type("RuSt").is_none());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("before:"));
+}
+
+#[test]
+fn decode_all_prints_every_chunk_of_the_type() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    for message in ["one", "two", "three"] {
+        png.append_chunk(pngme::chunk::Chunk::new(
+            "ruSt".parse().unwrap(),
+            message.as_bytes().to_vec(),
+        ));
+    }
+    let output = pngme_with_stdin(&["decode", "-", "ruSt", "--all"], &png.as_bytes());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "one\ntwo\nthree\n"
+    );
+    let output = pngme_with_stdin(&["decode", "-", "ruSt"], &png.as_bytes());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "one\n");
+}
+
+#[test]
+fn remove_deletes_every_chunk_of_the_type_unless_first() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    for message in ["one", "two", "three"] {
+        png.append_chunk(pngme::chunk::Chunk::new(
+            "ruSt".parse().unwrap(),
+            message.as_bytes().to_vec(),
+        ));
+    }
+    let bytes = png.as_bytes();
+    let output = pngme_with_stdin(&["remove", "-", "ruSt", "--output-file", "-"], &bytes);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Removed 3 ruSt chunks"));
+    let removed = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert!(removed.chunks_by_type("ruSt").is_empty());
+
+    let output = pngme_with_stdin(
+        &["remove", "-", "ruSt", "--first", "--output-file", "-"],
+        &bytes,
+    );
+    let removed = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(removed.chunks_by_type("ruSt").len(), 2);
+}
+
+#[test]
+fn assume_png_reads_a_chunk_stream_without_signature() {
+    let stream = &DICE[8..];
+    assert!(!pngme_with_stdin(&["list", "-"], stream).status.success());
+    let output = pngme_with_stdin(&["list", "-", "--no-signature"], stream);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .ends_with("7 chunks\n"));
+    let output = pngme_with_stdin(&["print", "-", "--assume-png"], stream);
+    assert!(output.status.success());
+}
+
+#[test]
+fn print_and_list_json_parse_back_to_the_chunks() {
+    use pngme::json::Json;
+
+    for args in [["print", "-", "--json"], ["list", "-", "--json"]] {
+        let output = pngme_with_stdin(&args, DICE);
+        assert!(output.status.success());
+        let json = Json::parse(&String::from_utf8(output.stdout).unwrap()).unwrap();
+        let Json::Array(chunks) = json else {
+            panic!("expected an array, got {json}");
+        };
+        let types = chunks
+            .iter()
+            .map(|chunk| chunk.get("type").unwrap().clone())
+            .collect::<Vec<_>>();
+        let expected = ["IHDR", "sRGB", "gAMA", "pHYs", "IDAT", "RuSt", "IEND"].map(Json::from);
+        assert_eq!(types, expected);
+        assert_eq!(chunks[0].get("critical"), Some(&Json::Bool(true)));
+        assert_eq!(chunks[0].get("length"), Some(&Json::Number(13.0)));
+    }
+}
+
+#[test]
+fn count_prints_the_number_of_chunks_of_a_type() {
+    let count = |png: &[u8], chunk_type: &str| {
+        let output = pngme_with_stdin(&["count", "-", chunk_type], png);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(count(DICE, "ruSt"), "0\n");
+    assert_eq!(count(DICE, "RuSt"), "1\n");
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    for message in ["one", "two", "three"] {
+        png.append_chunk(pngme::chunk::Chunk::new(
+            "ruSt".parse().unwrap(),
+            message.as_bytes().to_vec(),
+        ));
+    }
+    assert_eq!(count(&png.as_bytes(), "ruSt"), "3\n");
+
+    let output = pngme_with_stdin(&["count", "-", "ru5t"], DICE);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn print_shows_background_as_hex_unless_decimal_colors() {
+    let output = pngme_with_stdin(&["encode", "-", "bKGD", "AP8AgAAA", "-", "--base64"], DICE);
+    assert!(output.status.success());
+    let print = |extra: &[&str]| {
+        let args = [&["print", "-"][..], extra].concat();
+        String::from_utf8(pngme_with_stdin(&args, &output.stdout).stdout).unwrap()
+    };
+    assert!(print(&[]).contains("decoded: background #ff8000"));
+    assert!(print(&["--decimal-colors"]).contains("decoded: background rgb(255, 128, 0)"));
+}
+
+#[test]
+fn list_shows_a_row_per_chunk_and_a_total() {
+    let output = pngme_with_stdin(&["list", "-"], DICE);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("   0  IHDR  critical "));
+    assert!(lines[2].contains("gAMA  ancillary"));
+    assert!(lines[6].starts_with("   6  IEND  critical "));
+    assert_eq!(lines[7], "7 chunks");
+    assert!(!stdout.contains('['));
+}
+
+#[test]
+fn decode_printable_only_escapes_control_bytes() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    png.append_chunk(pngme::chunk::Chunk::new(
+        "ruSt".parse().unwrap(),
+        b"red \x1b[31mtext\x07\xff\n".to_vec(),
+    ));
+    let output = pngme_with_stdin(
+        &["decode", "-", "ruSt", "--printable-only"],
+        &png.as_bytes(),
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "red \\x1b[31mtext\\x07\\xff\n\n"
+    );
+}
+
+#[test]
+fn encode_compress_round_trips_through_decode_decompress() {
+    let message = "all work and no play ".repeat(40);
+    let output = pngme_with_stdin(
+        &[
+            "encode",
+            "-",
+            "ruSt",
+            &message,
+            "-",
+            "--compress",
+            "--with-checksum",
+        ],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert!((png.chunk_by_type("ruSt").unwrap().length() as usize) < message.len());
+
+    let decoded = pngme_with_stdin(
+        &["decode", "-", "ruSt", "--decompress", "--verify-checksum"],
+        &output.stdout,
+    );
+    assert!(decoded.status.success());
+    assert_eq!(String::from_utf8(decoded.stdout).unwrap(), message + "\n");
+
+    let not_compressed = pngme_with_stdin(&["decode", "-", "RuSt", "--decompress"], DICE);
+    assert!(!not_compressed.status.success());
+    assert!(String::from_utf8(not_compressed.stderr)
+        .unwrap()
+        .contains("not compressed"));
+}
+
+#[test]
+fn decode_checks_the_checksum_only_when_asked() {
+    let checked = pngme_with_stdin(
+        &["encode", "-", "ruSt", "hello", "-", "--with-checksum"],
+        DICE,
+    );
+    assert!(checked.status.success());
+    let verified = pngme_with_stdin(
+        &["decode", "-", "ruSt", "--verify-checksum"],
+        &checked.stdout,
+    );
+    assert!(verified.status.success());
+    assert_eq!(verified.stdout, b"hello\n");
+    assert!(String::from_utf8(verified.stderr)
+        .unwrap()
+        .contains("message integrity OK"));
+
+    // Ordinary text that happens to end like a checksum trailer
+    let message = format!("{}PMSH", "x".repeat(40));
+    let plain = pngme_with_stdin(&["encode", "-", "ruSt", &message, "-"], DICE);
+    assert!(plain.status.success());
+    let decoded = pngme_with_stdin(&["decode", "-", "ruSt"], &plain.stdout);
+    assert!(decoded.status.success());
+    assert_eq!(String::from_utf8(decoded.stdout).unwrap(), message + "\n");
+
+    let failed = pngme_with_stdin(&["decode", "-", "ruSt", "--verify-checksum"], &plain.stdout);
+    assert!(!failed.status.success());
+    assert!(String::from_utf8(failed.stderr)
+        .unwrap()
+        .contains("message integrity FAILED"));
+
+    let missing = pngme_with_stdin(&["decode", "-", "RuSt", "--verify-checksum"], DICE);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8(missing.stderr)
+        .unwrap()
+        .contains("no checksum"));
+}
+
+#[test]
+fn remove_recompute_crcs_fixes_bad_crcs() {
+    let mut bytes = DICE.to_vec();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    let strict = pngme_with_stdin(&["remove", "-", "RuSt", "--output-file", "-"], &bytes);
+    assert!(!strict.status.success());
+
+    let output = pngme_with_stdin(
+        &[
+            "remove",
+            "-",
+            "RuSt",
+            "--recompute-crcs",
+            "--output-file",
+            "-",
+        ],
+        &bytes,
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Fixed 1 chunk CRCs"));
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert!(png.chunk_by_type("IEND").is_some());
+}
+
+#[test]
+fn encode_passphrase_round_trips_and_rejects_wrong_passphrase() {
+    let output = pngme_with_stdin(
+        &[
+            "encode",
+            "-",
+            "ruSt",
+            "meet at dawn",
+            "-",
+            "--passphrase",
+            "hunter2",
+        ],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    let data = png.chunk_by_type("ruSt").unwrap().data();
+    assert!(!data.windows(4).any(|window| window == b"meet"));
+
+    let decoded = pngme_with_stdin(
+        &["decode", "-", "ruSt", "--passphrase", "hunter2"],
+        &output.stdout,
+    );
+    assert!(decoded.status.success());
+    assert_eq!(String::from_utf8(decoded.stdout).unwrap(), "meet at dawn\n");
+
+    let wrong = pngme_with_stdin(
+        &["decode", "-", "ruSt", "--passphrase", "hunter3"],
+        &output.stdout,
+    );
+    assert!(!wrong.status.success());
+    assert!(wrong.stdout.is_empty());
+    assert!(String::from_utf8(wrong.stderr)
+        .unwrap()
+        .contains("Authentication failed"));
+}
+
+#[test]
+fn encode_and_remove_process_every_file_matching_a_glob() {
+    let dir = std::env::temp_dir().join("pngme-batch-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["a.png", "b.png"] {
+        std::fs::write(dir.join(name), DICE).unwrap();
+    }
+    let pattern = dir.join("*.png");
+    let pattern = pattern.to_str().unwrap();
+    let output = pngme_with_stdin(&["encode", pattern, "ruSt", "watermark", "--in-place"], &[]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("2 succeeded, 0 failed"));
+    for name in ["a.png", "b.png"] {
+        let png = pngme::png::Png::try_from(&std::fs::read(dir.join(name)).unwrap()[..]).unwrap();
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"watermark");
+    }
+
+    std::fs::write(dir.join("c.png"), b"not a png").unwrap();
+    let out_dir = dir.join("out");
+    let output = pngme_with_stdin(
+        &[
+            "remove",
+            pattern,
+            "ruSt",
+            "--output-dir",
+            out_dir.to_str().unwrap(),
+        ],
+        &[],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("c.png"));
+    assert!(stderr.contains("2 succeeded, 1 failed"));
+    for name in ["a.png", "b.png"] {
+        let png =
+            pngme::png::Png::try_from(&std::fs::read(out_dir.join(name)).unwrap()[..]).unwrap();
+        assert!(png.chunk_by_type("ruSt").is_none());
+        let source =
+            pngme::png::Png::try_from(&std::fs::read(dir.join(name)).unwrap()[..]).unwrap();
+        assert!(source.chunk_by_type("ruSt").is_some());
+    }
+    assert!(!out_dir.join("c.png").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn optimize_shrinks_the_file_without_changing_pixels() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    png.split_idat(1000).unwrap();
+    let text = pngme::chunk::Chunk::new("tEXt".parse().unwrap(), b"Title\0dice".to_vec());
+    png.append_chunk(text.clone());
+    png.append_chunk(text);
+    png.append_chunk(pngme::chunk::Chunk::new(
+        "ruSt".parse().unwrap(),
+        Vec::new(),
+    ));
+    let bytes = png.as_bytes();
+
+    let output = pngme_with_stdin(&["optimize", "-", "--output-file", "-"], &bytes);
+    assert!(output.status.success());
+    assert!(output.stdout.len() < bytes.len());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("removed 1 duplicate and 1 empty chunks"));
+    assert!(stderr.contains(&format!(
+        "Saved {} bytes",
+        bytes.len() - output.stdout.len()
+    )));
+
+    let optimized = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(optimized.chunks_by_type("IDAT").len(), 1);
+    assert_eq!(optimized.chunks_by_type("tEXt").len(), 1);
+    let before = pngme::raster::Raster::try_from(&png).unwrap();
+    let after = pngme::raster::Raster::try_from(&optimized).unwrap();
+    assert_eq!(before.first_difference(&after).unwrap(), None);
+}
+
+#[test]
+fn extract_writes_raw_chunk_data() {
+    let dir = std::env::temp_dir().join("pngme-extract-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    for data in [BINARY, b"second"] {
+        png.append_chunk(pngme::chunk::Chunk::new(
+            "ruSt".parse().unwrap(),
+            data.to_vec(),
+        ));
+    }
+    let bytes = png.as_bytes();
+
+    let output = pngme_with_stdin(&["extract", "-", "ruSt", "-"], &bytes);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, BINARY);
+
+    let out = dir.join("out.bin");
+    let output = pngme_with_stdin(&["extract", "-", "ruSt:1", out.to_str().unwrap()], &bytes);
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(&out).unwrap(), b"second");
+
+    let output = pngme_with_stdin(
+        &["extract", "-", "ruSt", out.to_str().unwrap(), "--all"],
+        &bytes,
+    );
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(dir.join("out.0.bin")).unwrap(), BINARY);
+    assert_eq!(std::fs::read(dir.join("out.1.bin")).unwrap(), b"second");
+
+    let output = pngme_with_stdin(&["extract", "-", "tEXt", "-"], &bytes);
+    assert!(!output.status.success());
+
+    let output = pngme_with_stdin(&["extract", "-", "ruSt:1", "-", "--spool"], &bytes);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"second");
+    let spooled = dir.join("spooled.bin");
+    let output = pngme_with_stdin(
+        &["extract", "-", "ruSt", spooled.to_str().unwrap(), "--spool"],
+        &bytes,
+    );
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(&spooled).unwrap(), BINARY);
+
+    let mut corrupt = bytes.clone();
+    let last = corrupt.len() - 13;
+    corrupt[last] ^= 1;
+    let output = pngme_with_stdin(
+        &[
+            "extract",
+            "-",
+            "ruSt:1",
+            spooled.to_str().unwrap(),
+            "--spool",
+        ],
+        &corrupt,
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!spooled.exists());
+    let output = pngme_with_stdin(&["extract", "-", "tEXt", "-", "--spool"], &bytes);
+    assert_eq!(output.status.code(), Some(1));
+
+    let mut trailing = bytes.clone();
+    trailing.extend(b"not a chunk");
+    let output = pngme_with_stdin(
+        &[
+            "extract",
+            "-",
+            "ruSt:1",
+            spooled.to_str().unwrap(),
+            "--spool",
+        ],
+        &trailing,
+    );
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(&spooled).unwrap(), b"second");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn quiet_and_verbose_control_stderr() {
+    let encode = |flag: &[&str]| {
+        let args = [&["encode", "-", "ruST", "hi", "-"][..], flag].concat();
+        let output = pngme_with_stdin(&args, DICE);
+        assert!(output.status.success());
+        (output.stdout, String::from_utf8(output.stderr).unwrap())
+    };
+    let (png, stderr) = encode(&[]);
+    assert!(stderr.contains("warning: ruST is not marked safe-to-copy"));
+    assert!(!stderr.contains("Added"));
+    let (_, stderr) = encode(&["--quiet"]);
+    assert_eq!(stderr, "");
+    let (_, stderr) = encode(&["--verbose"]);
+    assert!(stderr.contains("warning: ruST is not marked safe-to-copy"));
+    assert!(stderr.contains("Added ruST chunk (2 bytes) at index 6"));
+    assert!(stderr.contains("Wrote the PNG to stdout"));
+
+    let remove = |flag: &str| {
+        let output = pngme_with_stdin(&["remove", "-", "ruST", "--output-file", "-", flag], &png);
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    assert_eq!(remove("--quiet"), "");
+    let stderr = remove("--verbose");
+    assert!(stderr.contains("Removed 1 ruST chunks"));
+    assert!(stderr.contains("Removed ruST chunk (2 bytes)"));
+
+    let output = pngme_with_stdin(&["-q", "decode", "-", "ruST"], &png);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "hi\n");
+    assert!(output.stderr.is_empty());
+
+    assert!(
+        !pngme_with_stdin(&["decode", "-", "ruST", "-q", "-v"], &png)
+            .status
+            .success()
+    );
+}
+
+#[test]
+fn clean_keeps_only_critical_chunks() {
+    let output = pngme_with_stdin(&["clean", "-", "--output-file", "-"], DICE);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Stripped 3 ancillary chunks"));
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    let types = png
+        .chunks()
+        .iter()
+        .map(|chunk| chunk.chunk_type().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(types, ["IHDR", "IDAT", "RuSt", "IEND"]);
+    assert!(pngme::raster::Raster::try_from(&png).is_ok());
+}
+
+#[test]
+fn encode_refuses_a_second_iend() {
+    let output = pngme_with_stdin(&["encode", "-", "IEND", "", "-"], DICE);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("already has an IEND chunk"));
+
+    for args in [
+        &["encode", "-", "IEND", "hi", "-", "--index", "1"][..],
+        &["encode", "-", "IHDR", "hi", "-", "--index", "1"],
+        &["encode", "-", "IEND", "hi", "-", "--random-position"],
+    ] {
+        let output = pngme_with_stdin(args, DICE);
+        assert!(!output.status.success(), "{args:?}");
+        assert!(output.stdout.is_empty());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("chunk, and only one is allowed"));
+    }
+}
+
+#[test]
+fn replace_keeps_the_chunk_in_place() {
+    let output = pngme_with_stdin(
+        &["replace", "-", "RuSt", "new message", "--output-file", "-"],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    let original = pngme::png::Png::try_from(DICE).unwrap();
+    assert_eq!(png.chunks().len(), original.chunks().len());
+    assert_eq!(png.chunks()[5].chunk_type().to_string(), "RuSt");
+    assert_eq!(png.chunks()[5].data(), b"new message");
+
+    let output = pngme_with_stdin(&["replace", "-", "ruSt", "hi", "--output-file", "-"], DICE);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Chunk not found: ruSt"));
+
+    let args = [
+        "replace",
+        "-",
+        "ruSt",
+        "hi",
+        "--create",
+        "--output-file",
+        "-",
+    ];
+    let output = pngme_with_stdin(&args, DICE);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(png.chunks()[6].data(), b"hi");
+}
+
+#[test]
+fn dry_run_reports_the_change_without_writing() {
+    let path = std::env::temp_dir().join("pngme-dry-run-test.png");
+    std::fs::write(&path, DICE).unwrap();
+    let file = path.to_str().unwrap();
+    let output = pngme_with_stdin(&["encode", file, "ruSt", "hi", "-f", "--dry-run"], &[]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Added ruSt chunk (2 bytes) at index 6"));
+    assert!(stderr.contains(&format!(
+        "Dry run: would write {} bytes to {file}, replacing {} bytes",
+        DICE.len() + 14,
+        DICE.len()
+    )));
+
+    let output = pngme_with_stdin(&["--dry-run", "remove", file, "RuSt", "--in-place"], &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Removed 1 RuSt chunks"));
+    assert_eq!(std::fs::read(&path).unwrap(), DICE);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn overwriting_the_input_needs_in_place() {
+    let path = std::env::temp_dir().join("pngme-in-place-test.png");
+    std::fs::write(&path, DICE).unwrap();
+    let file = path.to_str().unwrap();
+    for args in [
+        &["encode", file, "ruSt", "hi"][..],
+        &["remove", file, "RuSt"],
+        &["replace", file, "RuSt", "new"],
+        &["clean", file],
+        &["optimize", file],
+        &["fix-crc", file],
+        &["merge-idat", file],
+        &["split-idat", file, "--size", "1000"],
+        &["set-gamma", file, "0.45455"],
+        &["seal", file],
+        &["stash", file, file],
+    ] {
+        let output = pngme_with_stdin(args, &[]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("pass an output file or --in-place"));
+        assert_eq!(std::fs::read(&path).unwrap(), DICE);
+    }
+
+    let output = pngme_with_stdin(&["encode", file, "ruSt", "hi", "--force"], &[]);
+    assert!(output.status.success());
+    let output = pngme_with_stdin(&["remove", file, "RuSt", "-f"], &[]);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&std::fs::read(&path).unwrap()[..]).unwrap();
+    assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"hi");
+    assert!(png.chunk_by_type("RuSt").is_none());
+
+    let output = pngme_with_stdin(&["clean", file, "--in-place"], &[]);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&std::fs::read(&path).unwrap()[..]).unwrap();
+    assert!(png.chunk_by_type("ruSt").is_none());
+
+    let output = pngme_with_stdin(&["stash", file, file, "--force"], &[]);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&std::fs::read(&path).unwrap()[..]).unwrap();
+    assert!(png.chunk_by_type("stSh").is_some());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn text_keyword_round_trips_through_a_text_chunk() {
+    let output = pngme_with_stdin(
+        &[
+            "encode",
+            "-",
+            "tEXt",
+            "Dice on a table",
+            "-",
+            "--text-keyword",
+            "Title",
+        ],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert_eq!(
+        png.chunk_by_type("tEXt").unwrap().data(),
+        b"Title\0Dice on a table"
+    );
+
+    let decoded = pngme_with_stdin(&["decode", "-", "tEXt"], &output.stdout);
+    assert!(decoded.status.success());
+    assert_eq!(decoded.stdout, b"Title: Dice on a table\n");
+
+    let wrong_type = pngme_with_stdin(
+        &["encode", "-", "ruSt", "hi", "-", "--text-keyword", "Title"],
+        DICE,
+    );
+    assert!(!wrong_type.status.success());
+}
+
+#[test]
+fn diff_reports_an_added_chunk() {
+    let dir = std::env::temp_dir().join("pngme-diff-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let (original, edited) = (dir.join("original.png"), dir.join("edited.png"));
+    std::fs::write(&original, DICE).unwrap();
+    let (original, edited) = (original.to_str().unwrap(), edited.to_str().unwrap());
+
+    let same = pngme_with_stdin(&["diff", original, original], &[]);
+    assert!(same.status.success());
+    assert_eq!(same.stdout, b"Chunks identical\n");
+
+    let output = pngme_with_stdin(&["encode", original, "ruSt", "hi", edited], &[]);
+    assert!(output.status.success());
+    let diff = pngme_with_stdin(&["diff", original, edited], &[]);
+    assert_eq!(diff.status.code(), Some(1));
+    assert_eq!(diff.stdout, b"added ruSt at index 6\n");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failures_exit_with_documented_codes() {
+    let missing = pngme_with_stdin(&["decode", "-", "ruSt"], DICE);
+    assert_eq!(missing.status.code(), Some(1));
+    assert_eq!(missing.stderr, b"Error: Chunk not found: ruSt\n");
+
+    let not_png = pngme_with_stdin(&["decode", "-", "ruSt"], b"GIF89a, not a PNG");
+    assert_eq!(not_png.status.code(), Some(2));
+    assert_eq!(
+        not_png.stderr,
+        b"Error: not a PNG file: invalid signature\n"
+    );
+
+    let truncated = pngme_with_stdin(&["decode", "-", "ruSt"], &DICE[..DICE.len() - 6]);
+    assert_eq!(truncated.status.code(), Some(2));
+
+    let empty = pngme_with_stdin(&["decode", "-", "ruSt"], &[]);
+    assert_eq!(empty.status.code(), Some(2));
+
+    let diagnosed = pngme_with_stdin(&["print", "-", "--diagnose"], &DICE[..DICE.len() - 6]);
+    assert_eq!(diagnosed.status.code(), Some(2));
+
+    let other = pngme_with_stdin(&["decode", "/nonexistent/pngme.png", "ruSt"], &[]);
+    assert_eq!(other.status.code(), Some(3));
+    let stderr = String::from_utf8(other.stderr).unwrap();
+    assert!(stderr.starts_with("Error: ") && stderr.lines().count() == 1);
+}
+
+#[test]
+fn print_truncates_chunk_data_unless_full() {
+    let output = pngme_with_stdin(&["print", "-"], DICE);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("chunk_data: 4681 bytes\n"));
+    assert!(stdout.contains("    ... 4617 more bytes\n"));
+    assert!(!stdout.contains("    00000040  "));
+
+    let output = pngme_with_stdin(&["print", "-", "--full"], DICE);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("more bytes"));
+    assert!(stdout.contains("    00001240  "));
+}
+
+#[test]
+fn output_file_naming_the_input_needs_in_place() {
+    let dir = std::env::temp_dir().join("pngme-output-file-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("img.png");
+    std::fs::write(&input, DICE).unwrap();
+    let same = dir.join(".").join("img.png");
+    let (input, same) = (input.to_str().unwrap(), same.to_str().unwrap());
+
+    let refused = pngme_with_stdin(&["encode", input, "ruSt", "hi", same], &[]);
+    assert!(!refused.status.success());
+    assert!(String::from_utf8(refused.stderr)
+        .unwrap()
+        .contains("is the input file, pass --in-place"));
+    let refused = pngme_with_stdin(&["remove", input, "RuSt", "--output-file", same], &[]);
+    assert!(!refused.status.success());
+    assert_eq!(std::fs::read(input).unwrap(), DICE);
+
+    let other = dir.join("other.png");
+    let other = other.to_str().unwrap();
+    let output = pngme_with_stdin(&["encode", input, "ruSt", "hi", other], &[]);
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(input).unwrap(), DICE);
+    let output = pngme_with_stdin(&["encode", input, "ruSt", "hi", same, "--in-place"], &[]);
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(input).unwrap(), std::fs::read(other).unwrap());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn decode_selects_a_chunk_by_index() {
+    let output = pngme_with_stdin(&["decode", "-", "--index", "5"], DICE);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(DICE).unwrap();
+    let expected = png.chunk_at(5).unwrap().data_as_string_lossy();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{expected}\n")
+    );
+
+    let out_of_range = pngme_with_stdin(&["decode", "-", "--index", "7"], DICE);
+    assert_eq!(out_of_range.status.code(), Some(1));
+    assert!(String::from_utf8(out_of_range.stderr)
+        .unwrap()
+        .contains("index 7, the file has 7 chunks"));
+
+    let both = pngme_with_stdin(&["decode", "-", "RuSt", "--index", "5"], DICE);
+    assert!(!both.status.success());
+    let neither = pngme_with_stdin(&["decode", "-"], DICE);
+    assert!(!neither.status.success());
+}
+
+#[test]
+fn stash_and_unstash_round_trip_a_binary_file() {
+    let dir = std::env::temp_dir().join("pngme-stash-test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let secret = dir.join("secret.zip");
+    let contents = (0..5000u32)
+        .map(|i| (i * 7 % 256) as u8)
+        .collect::<Vec<_>>();
+    std::fs::write(&secret, &contents).unwrap();
+    let (image, out) = (dir.join("image.png"), dir.join("out"));
+    let (image, out) = (image.to_str().unwrap(), out.to_str().unwrap());
+
+    let output = pngme_with_stdin(
+        &[
+            "stash",
+            "-",
+            secret.to_str().unwrap(),
+            "--chunk-size",
+            "1024",
+            "--output-file",
+            image,
+        ],
+        DICE,
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Stashed secret.zip (5000 bytes) in 6 stSh chunks"));
+
+    let output = pngme_with_stdin(&["unstash", image, out], &[]);
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read(dir.join("out").join("secret.zip")).unwrap(),
+        contents
+    );
+
+    let again = pngme_with_stdin(&["unstash", image, out], &[]);
+    assert!(!again.status.success());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn print_reports_trailing_bytes_and_encode_keeps_them() {
+    let mut bytes = DICE.to_vec();
+    bytes.extend(b"0123456789abcdef");
+    let output = pngme_with_stdin(&["print", "-"], &bytes);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("16 trailing bytes after IEND"));
+
+    let output = pngme_with_stdin(&["encode", "-", "ruSt", "hi", "-"], &bytes);
+    assert!(output.status.success());
+    assert!(output
+        .stdout
+        .ends_with(b"IEND\xae\x42\x60\x820123456789abcdef"));
+}
+
+#[test]
+fn case_insensitive_flag_matches_chunk_types_ignoring_case() {
+    let count = pngme_with_stdin(&["count", "-", "rust"], DICE);
+    assert_eq!(count.stdout, b"0\n");
+    let count = pngme_with_stdin(&["count", "-", "rust", "-i"], DICE);
+    assert_eq!(count.stdout, b"1\n");
+
+    let decode = pngme_with_stdin(&["decode", "-", "rust"], DICE);
+    assert_eq!(decode.status.code(), Some(1));
+    let decode = pngme_with_stdin(&["decode", "-", "rust", "-i"], DICE);
+    assert!(decode.status.success());
+
+    let remove = pngme_with_stdin(&["remove", "-", "rust", "-i", "--output-file", "-"], DICE);
+    assert!(remove.status.success());
+    let png = pngme::png::Png::try_from(remove.stdout.as_slice()).unwrap();
+    assert!(png.chunk_by_type("RuSt").is_none());
+}
+
+#[test]
+fn removing_a_critical_chunk_needs_yes() {
+    let refused = pngme_with_stdin(&["remove", "-", "IDAT", "--output-file", "-"], DICE);
+    assert!(!refused.status.success());
+    assert!(refused.stdout.is_empty());
+    assert!(String::from_utf8(refused.stderr)
+        .unwrap()
+        .contains("Removing critical chunk IDAT breaks the PNG, pass --yes"));
+    let by_index = pngme_with_stdin(&["remove", "-", "@4", "--output-file", "-"], DICE);
+    assert!(!by_index.status.success());
+
+    let confirmed = pngme_with_stdin(
+        &["remove", "-", "IDAT", "--yes", "--output-file", "-"],
+        DICE,
+    );
+    assert!(confirmed.status.success());
+    let png = pngme::png::Png::try_from(confirmed.stdout.as_slice()).unwrap();
+    assert!(png.chunk_by_type("IDAT").is_none());
+
+    let encoded = pngme_with_stdin(&["encode", "-", "ruSt", "hi", "-"], DICE);
+    let ancillary = pngme_with_stdin(
+        &["remove", "-", "ruSt", "--output-file", "-"],
+        &encoded.stdout,
+    );
+    assert!(ancillary.status.success());
+    // RuSt has the critical bit set but isn't a standard chunk
+    let custom = pngme_with_stdin(&["remove", "-", "RuSt", "--output-file", "-"], DICE);
+    assert!(custom.status.success());
+}
+
+#[test]
+fn scan_prints_only_ancillary_chunks_holding_text() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    for (chunk_type, data) in [
+        ("ruSt", b"first message".to_vec()),
+        ("biNa", vec![0xff, 0xfe, 0x00, 0x80]),
+        ("noTe", b"second message".to_vec()),
+    ] {
+        png.append_chunk(pngme::chunk::Chunk::new(chunk_type.parse().unwrap(), data));
+    }
+    let output = pngme_with_stdin(&["scan", "-"], &png.as_bytes());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "ruSt: first message\nnoTe: second message\n"
+    );
+}
+
+#[test]
+fn type_filter_limits_print_and_list_to_the_given_types() {
+    let list = |args: &[&str]| {
+        let output = pngme_with_stdin(&[&["list", "-"], args].concat(), DICE);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let single = list(&["--type", "RuSt"]);
+    assert_eq!(single.lines().count(), 2);
+    assert!(single.contains("RuSt"));
+    assert!(single.ends_with("1 chunks\n"));
+    let several = list(&["--type", "RuSt", "--type", "gAMA"]);
+    assert!(several.contains("gAMA") && several.contains("RuSt"));
+    assert!(!several.contains("IDAT"));
+    assert!(several.ends_with("2 chunks\n"));
+
+    let print = pngme_with_stdin(&["print", "-", "--type", "RuSt"], DICE);
+    assert!(print.status.success());
+    let stdout = String::from_utf8(print.stdout).unwrap();
+    assert_eq!(stdout.matches("chunk_type:").count(), 1);
+    assert!(stdout.contains("chunk_type: RuSt"));
+    assert!(stdout.contains(&format!("Size: {} bytes in 7 chunks", DICE.len())));
+    let print = pngme_with_stdin(&["print", "-", "--type", "IHDR", "--type", "IEND"], DICE);
+    let stdout = String::from_utf8(print.stdout).unwrap();
+    assert_eq!(stdout.matches("chunk_type:").count(), 2);
+
+    for command in ["list", "print"] {
+        let malformed = pngme_with_stdin(&[command, "-", "--type", "ru5t"], DICE);
+        assert!(!malformed.status.success());
+        assert!(String::from_utf8(malformed.stderr)
+            .unwrap()
+            .contains("invalid chunk type \"ru5t\""));
+    }
+}
+
+#[test]
+fn deterministic_encryption_is_reproducible_and_the_default_is_not() {
+    let encode = |extra: &[&str]| {
+        let args = [
+            &[
+                "encode",
+                "-",
+                "ruSt",
+                "meet at dawn",
+                "-",
+                "--passphrase",
+                "hunter2",
+            ],
+            extra,
+        ]
+        .concat();
+        let output = pngme_with_stdin(&args, DICE);
+        assert!(output.status.success());
+        output
+    };
+    let first = encode(&["--deterministic"]);
+    assert!(String::from_utf8(first.stderr)
+        .unwrap()
+        .contains("warning: --deterministic"));
+    assert_eq!(first.stdout, encode(&["--deterministic"]).stdout);
+    assert_ne!(encode(&[]).stdout, encode(&[]).stdout);
+
+    let decoded = pngme_with_stdin(
+        &["decode", "-", "ruSt", "--passphrase", "hunter2"],
+        &first.stdout,
+    );
+    assert_eq!(String::from_utf8(decoded.stdout).unwrap(), "meet at dawn\n");
+}
+
+#[test]
+fn trace_prints_a_line_per_chunk_parsed() {
+    let traced = |output: Output| {
+        assert!(output.status.success());
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("trace: "))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let lines = traced(pngme_with_stdin(&["print", "-", "--trace"], DICE));
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[0], "trace: offset 8: IHDR chunk, length 13");
+    assert_eq!(lines[6], "trace: offset 4791: IEND chunk, length 0");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pngme"))
+        .args(["print", "-"])
+        .env("PNGME_TRACE", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(DICE).unwrap();
+    assert_eq!(traced(child.wait_with_output().unwrap()).len(), 7);
+
+    assert!(traced(pngme_with_stdin(&["print", "-"], DICE)).is_empty());
+}
+
+#[test]
+fn encode_joins_message_parts_and_reads_message_files() {
+    let decode = |png: &[u8]| {
+        let output = pngme_with_stdin(&["decode", "-", "ruSt"], png);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let joined = pngme_with_stdin(
+        &[
+            "encode",
+            "-",
+            "ruSt",
+            "-m",
+            "MIT License",
+            "-m",
+            "",
+            "-m",
+            "Copyright (c)",
+            "-",
+        ],
+        DICE,
+    );
+    assert!(joined.status.success());
+    assert_eq!(decode(&joined.stdout), "MIT License\n\nCopyright (c)\n");
+
+    let dir = std::env::temp_dir().join("pngme-message-file-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let note = dir.join("note.txt");
+    std::fs::write(&note, "first line\n  indented line\n").unwrap();
+    let from_file = pngme_with_stdin(
+        &[
+            "encode",
+            "-",
+            "ruSt",
+            "--message-file",
+            note.to_str().unwrap(),
+            "-",
+        ],
+        DICE,
+    );
+    assert!(from_file.status.success());
+    assert_eq!(decode(&from_file.stdout), "first line\n  indented line\n\n");
+    let png = pngme::png::Png::try_from(&from_file.stdout[..]).unwrap();
+    assert_eq!(
+        png.chunk_by_type("ruSt").unwrap().data(),
+        b"first line\n  indented line\n"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let both = pngme_with_stdin(&["encode", "-", "ruSt", "hi", "-", "-m", "there"], DICE);
+    assert!(!both.status.success());
+}
+
+#[test]
+fn decode_contains_selects_the_first_chunk_holding_the_text() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    for message in ["v1: old key", "v2: new key", "v2: newer key"] {
+        png.append_chunk(pngme::chunk::Chunk::new(
+            "ruSt".parse().unwrap(),
+            message.as_bytes().to_vec(),
+        ));
+    }
+    let bytes = png.as_bytes();
+    let decode =
+        |args: &[&str]| pngme_with_stdin(&[&["decode", "-", "ruSt"], args].concat(), &bytes);
+
+    let output = decode(&["--contains", "v2:"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"v2: new key\n");
+    let output = decode(&["--contains", "v2:", "--all"]);
+    assert_eq!(output.stdout, b"v2: new key\nv2: newer key\n");
+
+    let missing = decode(&["--contains", "v3:"]);
+    assert_eq!(missing.status.code(), Some(1));
+    assert!(String::from_utf8(missing.stderr)
+        .unwrap()
+        .contains("Chunk not found: ruSt containing \"v3:\""));
+}
+
+#[test]
+fn verify_and_print_flag_an_invalid_ihdr() {
+    let mut png = pngme::png::Png::try_from(DICE).unwrap();
+    let mut ihdr = png.chunk_by_type("IHDR").unwrap().data().to_vec();
+    ihdr[..4].copy_from_slice(&0u32.to_be_bytes());
+    png.replace_chunk_data("IHDR", ihdr).unwrap();
+    let bytes = png.as_bytes();
+
+    let verify = pngme_with_stdin(&["verify", "-"], &bytes);
+    assert_eq!(verify.status.code(), Some(1));
+    assert!(String::from_utf8(verify.stdout)
+        .unwrap()
+        .contains("IHDR dimensions must be non-zero, found 0x50"));
+    let print = pngme_with_stdin(&["print", "-"], &bytes);
+    assert!(print.status.success());
+    assert!(String::from_utf8(print.stderr)
+        .unwrap()
+        .contains("warning: IHDR dimensions must be non-zero"));
+
+    let verify = pngme_with_stdin(&["verify", "-"], DICE);
+    assert!(verify.status.success());
+}
+
+#[test]
+fn encode_append_from_copies_chunks_of_the_type_from_a_donor() {
+    let dir = std::env::temp_dir().join("pngme-append-from-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let donor_path = dir.join("donor.png");
+    let mut donor = pngme::png::Png::try_from(DICE).unwrap();
+    for (chunk_type, message) in [("ruSt", "first"), ("teXt", "other"), ("ruSt", "second")] {
+        donor.append_chunk(pngme::chunk::Chunk::new(
+            chunk_type.parse().unwrap(),
+            message.as_bytes().to_vec(),
+        ));
+    }
+    std::fs::write(&donor_path, donor.as_bytes()).unwrap();
+    let donor_arg = donor_path.to_str().unwrap();
+
+    let output = pngme_with_stdin(
+        &[
+            "encode",
+            "--append-from",
+            donor_arg,
+            "--type",
+            "ruSt",
+            "-",
+            "-",
+        ],
+        DICE,
+    );
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    let copied = png.chunks_by_type("ruSt");
+    assert_eq!(copied.len(), 2);
+    for (copied, original) in copied.iter().zip(donor.chunks_by_type("ruSt")) {
+        assert_eq!(copied.as_bytes(), original.as_bytes());
+    }
+    assert!(png.chunk_by_type("teXt").is_none());
+    assert_eq!(
+        png.chunks().last().unwrap().chunk_type().to_string(),
+        "IEND"
+    );
+
+    let missing = pngme_with_stdin(
+        &[
+            "encode",
+            "--append-from",
+            donor_arg,
+            "--type",
+            "noNe",
+            "-",
+            "-",
+        ],
+        DICE,
+    );
+    assert_eq!(missing.status.code(), Some(1));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn verify_reports_critical_chunks_after_iend() {
+    let mut bytes = DICE.to_vec();
+    let png = pngme::png::Png::try_from(DICE).unwrap();
+    for chunk_type in ["IDAT", "IEND"] {
+        bytes.extend(png.chunk_by_type(chunk_type).unwrap().as_bytes());
+    }
+    let output = pngme_with_stdin(&["verify", "-"], &bytes);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("critical chunk IDAT at index 7 comes after IEND"));
+    assert!(stdout.contains("9/9 chunks"));
+
+    let mut bytes = DICE.to_vec();
+    bytes.extend(b"not a chunk");
+    let output = pngme_with_stdin(&["verify", "-"], &bytes);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("warning: 11 bytes after IEND that are not chunks"));
+    let strict = pngme_with_stdin(&["verify", "-", "--strict"], &bytes);
+    assert_eq!(strict.status.code(), Some(1));
+}
+
+/// A 2x1 PNG with the given IHDR bit depth and color type, one scanline
+/// and any extra chunks before IDAT
+fn tiny_png(bit_depth: u8, color_type: u8, scanline: &[u8], extra: &[(&str, &[u8])]) -> Vec<u8> {
+    let chunk = |chunk_type: &str, data: &[u8]| {
+        pngme::chunk::Chunk::new(chunk_type.parse().unwrap(), data.to_vec())
+    };
+    let mut ihdr = vec![0, 0, 0, 2, 0, 0, 0, 1];
+    ihdr.extend([bit_depth, color_type, 0, 0, 0]);
+    let mut chunks = vec![chunk("IHDR", &ihdr)];
+    chunks.extend(
+        extra
+            .iter()
+            .map(|(chunk_type, data)| chunk(chunk_type, data)),
+    );
+    chunks.push(chunk("IDAT", &pngme::zlib::compress(scanline)));
+    chunks.push(chunk("IEND", &[]));
+    pngme::png::Png::from_chunks(chunks).as_bytes()
+}
+
+#[test]
+fn compare_image_expands_palettes_before_comparing() {
+    let dir = std::env::temp_dir().join("pngme-compare-image-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let palette: &[u8] = &[10, 20, 30, 200, 100, 50];
+    let indexed = tiny_png(8, 3, &[0, 0, 1], &[("PLTE", palette), ("tRNS", &[255, 0])]);
+    let rgba = tiny_png(8, 6, &[0, 10, 20, 30, 255, 200, 100, 50, 0], &[]);
+    let opaque = tiny_png(8, 6, &[0, 10, 20, 30, 255, 200, 100, 50, 255], &[]);
+    let (rgba_path, opaque_path) = (dir.join("rgba.png"), dir.join("opaque.png"));
+    std::fs::write(&rgba_path, rgba).unwrap();
+    std::fs::write(&opaque_path, opaque).unwrap();
+
+    let output = pngme_with_stdin(
+        &["compare-image", "-", rgba_path.to_str().unwrap()],
+        &indexed,
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Pixels identical\n"
+    );
+
+    let output = pngme_with_stdin(
+        &["compare-image", "-", opaque_path.to_str().unwrap()],
+        &indexed,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "First differing pixel at (1, 0)\n"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn remove_type_regex_needs_allow_critical_for_critical_chunks() {
+    let args = [
+        "remove",
+        "-",
+        "--type-regex",
+        "^[Rr]uSt$",
+        "--output-file",
+        "-",
+    ];
+    let output = pngme_with_stdin(&args, DICE);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Pattern matches critical chunk RuSt, pass --allow-critical to remove it"));
+
+    let output = pngme_with_stdin(&[&args[..], &["--allow-critical"]].concat(), DICE);
+    assert!(output.status.success());
+    let png = pngme::png::Png::try_from(&output.stdout[..]).unwrap();
+    assert!(png.chunk_by_type("RuSt").is_none());
+}
+
+#[test]
+fn color_and_verbosity_follow_the_environment_unless_overridden() {
+    let palette = tiny_png(8, 3, &[0, 0, 1], &[("PLTE", &[10, 20, 30, 200, 100, 50])]);
+    let swatch = |args: &[&str], color: &str| {
+        let args = [&["palette", "-", "--swatch"][..], args].concat();
+        let output = pngme_with_env(&args, &palette, &[("PNGME_COLOR", color)]);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert!(swatch(&[], "always").contains("\x1b[48;2;10;20;30m"));
+    assert_eq!(swatch(&[], "never"), "  0  #0a141e\n  1  #c86432\n");
+    assert!(!swatch(&["--color", "never"], "always").contains('\x1b'));
+    assert!(!swatch(&[], "auto").contains('\x1b'));
+
+    let encode = |quiet: &str, args: &[&str]| {
+        let args = [&["encode", "-", "ruSt", "hi", "-"][..], args].concat();
+        pngme_with_env(&args, DICE, &[("PNGME_QUIET", quiet)])
+    };
+    let quiet = encode("true", &[]);
+    assert!(quiet.status.success());
+    assert!(quiet.stderr.is_empty());
+    let verbose = encode("true", &["-v"]);
+    assert!(verbose.status.success());
+    assert!(!verbose.stderr.is_empty());
+}